@@ -0,0 +1,192 @@
+use crate::easing::Easing;
+use crate::timestep::lerp;
+use crate::transition::blend_colors;
+use crossterm::style::Color;
+
+// Anything an animation can move between two values
+pub trait Lerp: Copy {
+    fn lerp(from: Self, to: Self, t: f64) -> Self;
+}
+
+impl Lerp for f64 {
+    fn lerp(from: Self, to: Self, t: f64) -> Self {
+        lerp(from, to, t)
+    }
+}
+
+impl Lerp for (f64, f64) {
+    fn lerp(from: Self, to: Self, t: f64) -> Self {
+        (lerp(from.0, to.0, t), lerp(from.1, to.1, t))
+    }
+}
+
+impl Lerp for Color {
+    fn lerp(from: Self, to: Self, t: f64) -> Self {
+        blend_colors(from, to, t)
+    }
+}
+
+// A single move from one value to another
+#[derive(Clone, Copy)]
+pub struct Tween<T: Lerp> {
+    pub from: T,
+    pub to: T,
+    pub duration: f64,
+    pub easing: Easing,
+}
+
+impl<T: Lerp> Tween<T> {
+    pub fn new(from: T, to: T, duration: f64, easing: Easing) -> Tween<T> {
+        Tween { from, to, duration, easing }
+    }
+
+    pub fn sample(&self, elapsed: f64) -> T {
+        if self.duration <= 0.0 {
+            return self.to;
+        }
+        T::lerp(self.from, self.to, self.easing.apply(elapsed / self.duration))
+    }
+}
+
+// Tweens played back to back, each starting where the previous one ended
+pub struct Animation<T: Lerp> {
+    start: T,
+    tweens: Vec<Tween<T>>,
+    elapsed: f64,
+    looping: bool,
+}
+
+impl<T: Lerp> Animation<T> {
+    pub fn new(start: T) -> Animation<T> {
+        Animation {
+            start,
+            tweens: Vec::new(),
+            elapsed: 0.0,
+            looping: false,
+        }
+    }
+
+    // Moves on to the next keyframe
+    pub fn to(mut self, value: T, duration: f64, easing: Easing) -> Animation<T> {
+        let from = self.end();
+        self.tweens.push(Tween::new(from, value, duration, easing));
+        self
+    }
+
+    // Holds the current value for a while
+    pub fn delay(self, duration: f64) -> Animation<T> {
+        let value = self.end();
+        self.to(value, duration, Easing::Linear)
+    }
+
+    // Starts over from the first keyframe once the last one is done
+    pub fn looping(mut self) -> Animation<T> {
+        self.looping = true;
+        self
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.elapsed += dt;
+
+        let duration = self.duration();
+        if self.looping && duration > 0.0 {
+            self.elapsed %= duration;
+        }
+    }
+
+    pub fn value(&self) -> T {
+        let mut elapsed = self.elapsed;
+        for tween in &self.tweens {
+            if elapsed < tween.duration {
+                return tween.sample(elapsed);
+            }
+            elapsed -= tween.duration;
+        }
+        self.end()
+    }
+
+    pub fn duration(&self) -> f64 {
+        self.tweens.iter().map(|tween| tween.duration).sum()
+    }
+
+    // Looping animations never finish
+    pub fn is_finished(&self) -> bool {
+        !self.looping && self.elapsed >= self.duration()
+    }
+
+    pub fn restart(&mut self) {
+        self.elapsed = 0.0;
+    }
+
+    fn end(&self) -> T {
+        self.tweens.last().map_or(self.start, |tween| tween.to)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::easing::Easing;
+    use super::Animation;
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() < 1e-9
+    }
+
+    #[test]
+    fn keyframes_play_back_to_back() {
+        let mut animation = Animation::new(0.0).to(10.0, 1.0, Easing::Linear).to(0.0, 2.0, Easing::Linear);
+        assert_eq!(animation.duration(), 3.0);
+
+        animation.update(0.5);
+        assert!(close(animation.value(), 5.0));
+        animation.update(1.5);
+        assert!(close(animation.value(), 5.0));
+    }
+
+    #[test]
+    fn a_delay_holds_the_value() {
+        let mut animation = Animation::new(0.0).to(1.0, 1.0, Easing::Linear).delay(2.0).to(0.0, 1.0, Easing::Linear);
+
+        animation.update(1.5);
+        assert!(close(animation.value(), 1.0));
+        animation.update(1.0);
+        assert!(close(animation.value(), 1.0));
+        animation.update(1.0);
+        assert!(close(animation.value(), 0.5));
+    }
+
+    #[test]
+    fn finishes_on_the_last_value() {
+        let mut animation = Animation::new(0.0).to(4.0, 1.0, Easing::QuadOut);
+        animation.update(0.9);
+        assert!(!animation.is_finished());
+
+        animation.update(0.5);
+        assert!(animation.is_finished());
+        assert!(close(animation.value(), 4.0));
+    }
+
+    #[test]
+    fn looping_wraps_around_and_never_finishes() {
+        let mut animation = Animation::new(0.0).to(1.0, 1.0, Easing::Linear).to(0.0, 1.0, Easing::Linear).looping();
+
+        animation.update(2.25);
+        assert!(close(animation.value(), 0.25));
+        animation.update(10.0);
+        assert!(close(animation.value(), 0.25));
+        assert!(!animation.is_finished());
+    }
+
+    #[test]
+    fn restarting_goes_back_to_the_start() {
+        let mut animation = Animation::new(2.0).to(6.0, 1.0, Easing::Linear);
+        animation.update(5.0);
+        assert!(animation.is_finished());
+
+        animation.restart();
+        assert!(!animation.is_finished());
+        assert!(close(animation.value(), 2.0));
+        animation.update(0.5);
+        assert!(close(animation.value(), 4.0));
+    }
+}
@@ -0,0 +1,366 @@
+use std::cell::RefCell;
+use std::io::{Error, Stdout, Write};
+use std::rc::Rc;
+use crossterm::{cursor, queue, style, terminal};
+use crossterm::cursor::MoveTo;
+use crossterm::event::{EnableFocusChange, EnableMouseCapture, KeyboardEnhancementFlags, PushKeyboardEnhancementFlags};
+use crossterm::style::{Attribute, Attributes, Print, SetAttribute, SetAttributes, SetBackgroundColor, SetForegroundColor};
+use crossterm::terminal::{enable_raw_mode, supports_keyboard_enhancement, Clear, ClearType};
+use crate::input::KeyboardMode;
+use crate::canvas::Cell;
+use crate::terminal_guard::{restore_terminal, TerminalGuard};
+
+pub trait Backend {
+    fn init(&mut self) -> Result<(), Error>;
+    fn cleanup(&mut self) -> Result<(), Error>;
+
+    // Presents a finished frame with its top left corner at origin and returns the number of bytes it cost to do so
+    fn present(&mut self, cells: &[Cell], width: u16, height: u16, origin: (u16, u16)) -> Result<usize, Error>;
+
+    // Forces the next call to present to send the whole frame
+    fn invalidate(&mut self);
+
+    fn keyboard_mode(&self) -> KeyboardMode;
+}
+
+// Writes to stdout, or any other writer when a test wants to see the escape codes
+pub struct TerminalBackend<W: Write = Stdout> {
+    stdout: W,
+    prev_cells: Vec<Cell>,
+    frame: Vec<u8>,
+    keyboard_mode: KeyboardMode,
+    guard: Option<TerminalGuard>,
+}
+
+impl<W: Write> TerminalBackend<W> {
+    pub fn new(stdout: W) -> TerminalBackend<W> {
+        TerminalBackend {
+            stdout,
+            prev_cells: Vec::new(),
+            frame: Vec::new(),
+            keyboard_mode: KeyboardMode::Synthesized,
+            guard: None,
+        }
+    }
+}
+
+impl<W: Write> Backend for TerminalBackend<W> {
+    fn init(&mut self) -> Result<(), Error> {
+        let guard = TerminalGuard::new();
+        enable_raw_mode()?;
+
+        queue!(
+            self.stdout,
+            terminal::EnterAlternateScreen,
+            cursor::Hide,
+            EnableMouseCapture,
+            EnableFocusChange,
+            Clear(ClearType::All)
+        )?;
+
+        // Without the kitty keyboard protocol most terminals only ever report key presses
+        if supports_keyboard_enhancement().unwrap_or(false) {
+            queue!(
+                self.stdout,
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES
+                        | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                )
+            )?;
+            self.keyboard_mode = KeyboardMode::Enhanced;
+        } else if cfg!(windows) {
+            self.keyboard_mode = KeyboardMode::Native;
+        } else {
+            self.keyboard_mode = KeyboardMode::Synthesized;
+        }
+
+        guard.set_keyboard_enhanced(self.keyboard_mode == KeyboardMode::Enhanced);
+        self.guard = Some(guard);
+
+        // The terminal was just cleared, so the next frame has to be sent in full
+        self.invalidate();
+        Ok(())
+    }
+
+    fn cleanup(&mut self) -> Result<(), Error> {
+        let result = restore_terminal();
+        self.guard = None;
+        result
+    }
+
+    fn present(&mut self, cells: &[Cell], width: u16, height: u16, origin: (u16, u16)) -> Result<usize, Error> {
+        self.frame.clear();
+
+        // Clearing everything also blanks the border around a centered frame
+        let full_redraw = self.prev_cells.len() != cells.len();
+        if full_redraw {
+            queue!(self.frame, Clear(ClearType::All))?;
+        }
+
+        let mut color: Option<style::Color> = None;
+        let mut background: Option<style::Color> = None;
+        let mut attributes: Option<Attributes> = None;
+        let mut run = String::new();
+
+        for y in 0..height {
+            let mut x = 0;
+            while x < width {
+                let index = y as usize * width as usize + x as usize;
+                if !full_redraw && cells[index] == self.prev_cells[index] {
+                    x += 1;
+                    continue;
+                }
+
+                // Merge all consecutive changed cells on this row into a single write
+                queue!(self.frame, MoveTo(origin.0 + x, origin.1 + y))?;
+                while x < width {
+                    let index = y as usize * width as usize + x as usize;
+                    if !full_redraw && cells[index] == self.prev_cells[index] {
+                        break;
+                    }
+
+                    let cell = &cells[index];
+                    let cell_background = cell.background.unwrap_or(style::Color::Reset);
+                    let style_changed = attributes != Some(cell.attributes)
+                        || color != Some(cell.color)
+                        || background != Some(cell_background);
+
+                    if style_changed && !run.is_empty() {
+                        queue!(self.frame, Print(&run))?;
+                        run.clear();
+                    }
+
+                    if attributes != Some(cell.attributes) {
+                        // Resetting attributes also resets both colors
+                        queue!(self.frame, SetAttribute(Attribute::Reset), SetAttributes(cell.attributes))?;
+                        attributes = Some(cell.attributes);
+                        color = None;
+                        background = None;
+                    }
+
+                    if color != Some(cell.color) {
+                        queue!(self.frame, SetForegroundColor(cell.color))?;
+                        color = Some(cell.color);
+                    }
+
+                    if background != Some(cell_background) {
+                        queue!(self.frame, SetBackgroundColor(cell_background))?;
+                        background = Some(cell_background);
+                    }
+
+                    // The wide character before it already moved the cursor past this column
+                    if !cell.is_continuation() {
+                        run.push(cell.rune);
+                        run.push_str(&cell.combining);
+                    }
+                    x += 1;
+                }
+
+                if !run.is_empty() {
+                    queue!(self.frame, Print(&run))?;
+                    run.clear();
+                }
+            }
+        }
+
+        self.stdout.write_all(&self.frame)?;
+        self.stdout.flush()?;
+
+        self.prev_cells.clear();
+        self.prev_cells.extend_from_slice(cells);
+        Ok(self.frame.len())
+    }
+
+    fn invalidate(&mut self) {
+        self.prev_cells.clear();
+    }
+
+    fn keyboard_mode(&self) -> KeyboardMode {
+        self.keyboard_mode
+    }
+}
+
+#[derive(Default)]
+struct MemoryFrame {
+    width: u16,
+    height: u16,
+    cells: Vec<Cell>,
+}
+
+// Keeps the last presented frame in memory instead of writing to a terminal.
+// Clones share the same frame, so keep one around to inspect what the Screen drew.
+#[derive(Clone, Default)]
+pub struct MemoryBackend {
+    frame: Rc<RefCell<MemoryFrame>>,
+}
+
+impl MemoryBackend {
+    pub fn new() -> MemoryBackend {
+        MemoryBackend::default()
+    }
+
+    // Headless runs only print the text, tests look closer
+    #[cfg(test)]
+    pub fn size(&self) -> (u16, u16) {
+        let frame = self.frame.borrow();
+        (frame.width, frame.height)
+    }
+
+    #[cfg(test)]
+    pub fn cells(&self) -> Vec<Cell> {
+        self.frame.borrow().cells.clone()
+    }
+
+    #[cfg(test)]
+    pub fn cell(&self, x: u16, y: u16) -> Option<Cell> {
+        let frame = self.frame.borrow();
+        if x >= frame.width || y >= frame.height {
+            return None;
+        }
+
+        frame.cells.get(y as usize * frame.width as usize + x as usize).cloned()
+    }
+
+    pub fn text(&self) -> String {
+        let frame = self.frame.borrow();
+        let mut text = String::new();
+        for row in frame.cells.chunks(frame.width.max(1) as usize) {
+            let mut line = String::new();
+            for cell in row.iter().filter(|cell| !cell.is_continuation()) {
+                line.push(cell.rune);
+                line.push_str(&cell.combining);
+            }
+            text.push_str(line.trim_end());
+            text.push('\n');
+        }
+        text
+    }
+}
+
+impl Backend for MemoryBackend {
+    fn init(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn cleanup(&mut self) -> Result<(), Error> {
+        Ok(())
+    }
+
+    // Only the frame itself is kept, not where it would have gone in a terminal
+    fn present(&mut self, cells: &[Cell], width: u16, height: u16, _origin: (u16, u16)) -> Result<usize, Error> {
+        let mut frame = self.frame.borrow_mut();
+        frame.width = width;
+        frame.height = height;
+        frame.cells.clear();
+        frame.cells.extend_from_slice(cells);
+        Ok(0)
+    }
+
+    fn invalidate(&mut self) {}
+
+    // Scripted input sends its own release events
+    fn keyboard_mode(&self) -> KeyboardMode {
+        KeyboardMode::Native
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::cursor::MoveTo;
+    use crossterm::queue;
+    use crossterm::style::{Attribute, Attributes, Color, SetAttribute, SetAttributes, SetBackgroundColor, SetForegroundColor};
+    use crossterm::terminal::{Clear, ClearType};
+    use crate::canvas::Cell;
+    use crate::theme;
+    use super::{Backend, TerminalBackend};
+
+    const WIDTH: u16 = 8;
+    const HEIGHT: u16 = 3;
+
+    fn blank_frame() -> Vec<Cell> {
+        vec![Cell::blank(&theme::CLASSIC); (WIDTH * HEIGHT) as usize]
+    }
+
+    fn escape(command: impl crossterm::Command) -> String {
+        let mut bytes = Vec::new();
+        queue!(bytes, command).unwrap();
+        String::from_utf8(bytes).unwrap()
+    }
+
+    // What one present wrote, leaving the writer empty for the next
+    fn present(backend: &mut TerminalBackend<Vec<u8>>, cells: &[Cell]) -> String {
+        let written = backend.present(cells, WIDTH, HEIGHT, (0, 0)).unwrap();
+        let output = String::from_utf8(std::mem::take(&mut backend.stdout)).unwrap();
+        assert_eq!(written, output.len());
+        output
+    }
+
+    #[test]
+    fn an_unchanged_frame_writes_nothing() {
+        let mut backend = TerminalBackend::new(Vec::new());
+        let first = present(&mut backend, &blank_frame());
+        assert!(first.starts_with(&escape(Clear(ClearType::All))));
+
+        assert_eq!(present(&mut backend, &blank_frame()), "");
+    }
+
+    #[test]
+    fn only_changed_cells_are_sent() {
+        let mut backend = TerminalBackend::new(Vec::new());
+        present(&mut backend, &blank_frame());
+
+        let mut cells = blank_frame();
+        cells[(WIDTH + 3) as usize].rune = 'x';
+        let change = present(&mut backend, &cells);
+
+        // Each frame starts without knowing the terminal's style, so the one cell sets it in full
+        let theme = theme::CLASSIC;
+        let expected = [
+            escape(MoveTo(3, 1)),
+            escape(SetAttribute(Attribute::Reset)),
+            escape(SetAttributes(Attributes::default())),
+            escape(SetForegroundColor(theme.text)),
+            escape(SetBackgroundColor(theme.background)),
+            "x".to_string(),
+        ];
+        assert_eq!(change, expected.concat());
+    }
+
+    #[test]
+    fn neighbouring_changes_are_merged_into_one_run() {
+        let mut backend = TerminalBackend::new(Vec::new());
+        present(&mut backend, &blank_frame());
+
+        let mut cells = blank_frame();
+        for (i, c) in "abc".chars().enumerate() {
+            cells[2 + i].rune = c;
+        }
+        let change = present(&mut backend, &cells);
+
+        assert!(change.starts_with(&escape(MoveTo(2, 0))));
+        assert!(change.ends_with("abc"));
+        assert_eq!(change.matches("H").count(), 1);
+    }
+
+    #[test]
+    fn colors_are_only_set_when_they_change() {
+        let mut backend = TerminalBackend::new(Vec::new());
+        let mut cells = blank_frame();
+        for cell in cells.iter_mut().take(4) {
+            cell.color = Color::Red;
+        }
+        let full = present(&mut backend, &cells);
+
+        assert_eq!(full.matches(&escape(SetForegroundColor(Color::Red))).count(), 1);
+        assert_eq!(full.matches(&escape(SetForegroundColor(theme::CLASSIC.text))).count(), 1);
+    }
+
+    #[test]
+    fn invalidating_sends_the_whole_frame_again() {
+        let mut backend = TerminalBackend::new(Vec::new());
+        let first = present(&mut backend, &blank_frame());
+
+        backend.invalidate();
+        assert_eq!(present(&mut backend, &blank_frame()), first);
+    }
+}
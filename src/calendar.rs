@@ -0,0 +1,56 @@
+use chrono::{Datelike, NaiveDate};
+
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum DoorState {
+    Open,
+    Locked { days_left: i64 },
+}
+
+// Door N opens on the Nth of December in the year of `today`, this only depends on the date
+pub fn door_state(day: usize, today: NaiveDate) -> DoorState {
+    let unlocks = match NaiveDate::from_ymd_opt(today.year(), 12, day as u32) {
+        Some(date) => date,
+        None => return DoorState::Locked { days_left: i64::MAX },
+    };
+
+    let days_left = (unlocks - today).num_days();
+    if days_left <= 0 {
+        DoorState::Open
+    } else {
+        DoorState::Locked { days_left }
+    }
+}
+
+pub fn parse_date(text: &str) -> Result<NaiveDate, String> {
+    NaiveDate::parse_from_str(text, "%Y-%m-%d")
+        .map_err(|_| format!("'{}' is not a date, expected YYYY-MM-DD", text))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::testing::date;
+    use super::{door_state, DoorState};
+
+    #[test]
+    fn every_door_is_locked_the_day_before_december() {
+        assert_eq!(door_state(1, date(2024, 11, 30)), DoorState::Locked { days_left: 1 });
+        assert_eq!(door_state(24, date(2024, 11, 30)), DoorState::Locked { days_left: 24 });
+    }
+
+    #[test]
+    fn december_first_opens_only_the_first_door() {
+        assert_eq!(door_state(1, date(2024, 12, 1)), DoorState::Open);
+        assert_eq!(door_state(2, date(2024, 12, 1)), DoorState::Locked { days_left: 1 });
+    }
+
+    #[test]
+    fn every_door_is_open_on_christmas_eve() {
+        assert!((1..=24).all(|day| door_state(day, date(2024, 12, 24)) == DoorState::Open));
+    }
+
+    // Doors go by this year's December, so a new year starts the wait over
+    #[test]
+    fn january_waits_for_the_next_december() {
+        assert_eq!(door_state(1, date(2025, 1, 1)), DoorState::Locked { days_left: 334 });
+    }
+}
@@ -0,0 +1,410 @@
+use crossterm::style;
+use crossterm::style::Attributes;
+use crate::rect::Rect;
+use crate::theme::Theme;
+use crate::transition::{blend_colors, noise};
+use unicode_width::UnicodeWidthChar;
+
+// Fills the second column of a wide character, so it never gets printed itself
+pub const CONTINUATION: char = '\0';
+
+// Nothing drawn here yet, whatever is below shows through
+pub const TRANSPARENT: char = '\u{1}';
+
+#[derive(Clone, PartialEq)]
+pub struct Cell {
+    pub rune: char,
+    // Combining marks drawn on top of the rune, like accents
+    pub combining: String,
+    pub color: style::Color,
+    // None shows the background of whatever is below
+    pub background: Option<style::Color>,
+    pub attributes: Attributes,
+}
+
+impl Cell {
+    pub fn transparent() -> Cell {
+        Cell {
+            rune: TRANSPARENT,
+            combining: String::new(),
+            color: style::Color::Reset,
+            background: None,
+            attributes: Attributes::default(),
+        }
+    }
+
+    // An empty cell in the theme's colors
+    pub fn blank(theme: &Theme) -> Cell {
+        Cell {
+            rune: ' ',
+            combining: String::new(),
+            color: theme.text,
+            background: Some(theme.background),
+            attributes: Attributes::default(),
+        }
+    }
+
+    fn set(&mut self, c: char, color: style::Color) {
+        self.rune = c;
+        self.combining.clear();
+        self.color = color;
+    }
+
+    pub fn is_continuation(&self) -> bool {
+        self.rune == CONTINUATION
+    }
+
+    pub fn is_transparent(&self) -> bool {
+        self.rune == TRANSPARENT
+    }
+
+    // Nothing visible on top of the background
+    pub fn is_blank(&self) -> bool {
+        self.rune == ' ' || self.is_transparent()
+    }
+}
+
+// A grid of cells to draw into, which can be composited onto another
+#[derive(Clone)]
+pub struct Canvas {
+    width: i32,
+    height: i32,
+    cells: Vec<Cell>,
+    // Drawing outside the innermost of these is dropped
+    clips: Vec<Rect>,
+}
+
+impl Canvas {
+    // Starts out fully transparent
+    pub fn new(width: i32, height: i32) -> Canvas {
+        let mut canvas = Canvas {
+            width: 0,
+            height: 0,
+            cells: Vec::new(),
+            clips: Vec::new(),
+        };
+        canvas.resize(width, height);
+        canvas
+    }
+
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+
+    pub fn cells(&self) -> &[Cell] {
+        &self.cells
+    }
+
+    // Throws away everything drawn so far
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.width = width.max(0);
+        self.height = height.max(0);
+        self.cells.clear();
+        self.cells.resize((self.width * self.height) as usize, Cell::transparent());
+        self.clips.clear();
+    }
+
+    pub fn fill(&mut self, cell: &Cell) {
+        self.clips.clear();
+        for target in self.cells.iter_mut() {
+            target.clone_from(cell);
+        }
+    }
+
+    // The area drawing currently ends up in
+    pub fn clip(&self) -> Rect {
+        self.clips.last().copied().unwrap_or(self.bounds())
+    }
+
+    // Restricts drawing to a part of what's already allowed, until the matching pop
+    pub fn push_clip(&mut self, rect: Rect) {
+        let clip = self.clip().intersect(rect);
+        self.clips.push(clip);
+    }
+
+    pub fn pop_clip(&mut self) {
+        self.clips.pop();
+    }
+
+    // Wide characters take up this cell and the next, combining marks go on the cell before
+    pub fn set_cell(&mut self, x: i32, y: i32, c: char, color: style::Color) {
+        // Only ever written together with the wide character in front of it
+        if c == CONTINUATION {
+            return;
+        }
+
+        match c.width() {
+            Some(0) => self.attach(x, y, c),
+            Some(2) => {
+                let (Some(index), Some(next)) = (self.visible_index(x, y), self.visible_index(x + 1, y)) else {
+                    // Doesn't fit before the edge, so leave a gap rather than spill over
+                    self.set_narrow(x, y, ' ', color);
+                    return;
+                };
+
+                self.release(x, y);
+                self.release(x + 1, y);
+                self.cells[index].set(c, color);
+                self.cells[next].set(CONTINUATION, color);
+            }
+            _ => self.set_narrow(x, y, c, color),
+        }
+    }
+
+    pub fn cell(&self, x: i32, y: i32) -> Option<&Cell> {
+        self.visible_index(x, y).map(|index| &self.cells[index])
+    }
+
+    pub fn set_background(&mut self, x: i32, y: i32, color: style::Color) {
+        if let Some(index) = self.visible_index(x, y) {
+            self.cells[index].background = Some(color);
+        }
+    }
+
+    pub fn set_attributes(&mut self, x: i32, y: i32, attributes: Attributes) {
+        if let Some(index) = self.visible_index(x, y) {
+            self.cells[index].attributes = attributes;
+        }
+    }
+
+    // Lays another canvas over this one with its top left corner at (x, y). Transparent cells and
+    // missing backgrounds let this one show through. Below full opacity colors blend, and each
+    // character only shows once the opacity passes a threshold of its own, so text dissolves in.
+    pub fn draw_canvas(&mut self, canvas: &Canvas, x: i32, y: i32, opacity: f64) {
+        if opacity <= 0.0 {
+            return;
+        }
+
+        // Fully opaque keeps colors exactly as drawn, blending would turn named colors into rgb
+        let mix = |below: style::Color, above: style::Color| {
+            if opacity >= 1.0 { above } else { blend_colors(below, above, opacity) }
+        };
+
+        let area = self.clip().intersect(Rect::new(x, y, canvas.width, canvas.height));
+        for target_y in area.y..area.bottom() {
+            for target_x in area.x..area.right() {
+                let source = &canvas.cells[canvas.xy_to_index(target_x - x, target_y - y)];
+                if source.is_transparent() && source.background.is_none() {
+                    continue;
+                }
+
+                // Both halves of a wide character have to make the same choice
+                let owner_x = if source.is_continuation() { target_x - 1 } else { target_x };
+                let shows_rune = !source.is_transparent() && (opacity >= 1.0 || noise(owner_x as u32, target_y as u32) < opacity);
+
+                let index = self.xy_to_index(target_x, target_y);
+                let target = &mut self.cells[index];
+                if !source.is_transparent() {
+                    target.color = mix(target.color, source.color);
+                }
+                if shows_rune {
+                    target.rune = source.rune;
+                    target.combining.clone_from(&source.combining);
+                    target.attributes = source.attributes;
+                }
+                if let Some(background) = source.background {
+                    let below = target.background.unwrap_or(style::Color::Reset);
+                    target.background = Some(mix(below, background));
+                }
+            }
+        }
+    }
+
+    // Blanks halves of wide characters whose other half was drawn over by another canvas
+    pub fn repair_wide_characters(&mut self) {
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let index = self.xy_to_index(x, y);
+                let orphaned = if self.cells[index].is_continuation() {
+                    x == 0 || self.cells[index - 1].rune.width() != Some(2)
+                } else {
+                    self.cells[index].rune.width() == Some(2)
+                        && (x + 1 == self.width || !self.cells[index + 1].is_continuation())
+                };
+
+                if orphaned {
+                    let color = self.cells[index].color;
+                    self.cells[index].set(' ', color);
+                }
+            }
+        }
+    }
+
+    fn set_narrow(&mut self, x: i32, y: i32, c: char, color: style::Color) {
+        if let Some(index) = self.visible_index(x, y) {
+            self.release(x, y);
+            self.cells[index].set(c, color);
+        }
+    }
+
+    // Blanks whatever half of a wide character is left behind when the other half gets drawn over.
+    // That half may be just outside the clip, but it can't be left without the other.
+    fn release(&mut self, x: i32, y: i32) {
+        let Some(index) = self.canvas_index(x, y) else { return };
+
+        if self.cells[index].is_continuation() {
+            if let Some(owner) = self.canvas_index(x - 1, y) {
+                let color = self.cells[owner].color;
+                self.cells[owner].set(' ', color);
+            }
+            let color = self.cells[index].color;
+            self.cells[index].set(' ', color);
+        } else if let Some(next) = self.canvas_index(x + 1, y) {
+            if self.cells[next].is_continuation() {
+                let color = self.cells[next].color;
+                self.cells[next].set(' ', color);
+            }
+        }
+    }
+
+    fn attach(&mut self, x: i32, y: i32, mark: char) {
+        let mut x = x - 1;
+        if self.cell(x, y).is_some_and(Cell::is_continuation) {
+            x -= 1;
+        }
+
+        if let Some(index) = self.visible_index(x, y) {
+            self.cells[index].combining.push(mark);
+        }
+    }
+
+    fn visible_index(&self, x: i32, y: i32) -> Option<usize> {
+        if !self.clip().contains(x, y) {
+            return None;
+        }
+
+        Some(self.xy_to_index(x, y))
+    }
+
+    fn canvas_index(&self, x: i32, y: i32) -> Option<usize> {
+        self.bounds().contains(x, y).then(|| self.xy_to_index(x, y))
+    }
+
+    fn xy_to_index(&self, x: i32, y: i32) -> usize {
+        y as usize * self.width as usize + x as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+    use crate::rect::Rect;
+    use super::Canvas;
+
+    // A row as text, transparent cells as dots and without the second halves of wide characters.
+    // Reads past the clip, to see that nothing was drawn there.
+    fn row(canvas: &Canvas, y: i32) -> String {
+        let mut text = String::new();
+        for x in 0..canvas.width {
+            let cell = &canvas.cells[canvas.xy_to_index(x, y)];
+            if cell.is_continuation() {
+                continue;
+            }
+            text.push(if cell.is_transparent() { '.' } else { cell.rune });
+            text.push_str(&cell.combining);
+        }
+        text
+    }
+
+    fn draw(canvas: &mut Canvas, x: i32, y: i32, text: &str) {
+        for (row, line) in text.lines().enumerate() {
+            let mut column = x;
+            for c in line.chars() {
+                canvas.set_cell(column, y + row as i32, c, Color::White);
+                column += crate::drawing::char_width(c);
+            }
+        }
+    }
+
+    #[test]
+    fn drawing_over_the_left_half_of_a_wide_character_blanks_the_right() {
+        let mut canvas = Canvas::new(5, 1);
+        draw(&mut canvas, 1, 0, "世");
+        assert_eq!(row(&canvas, 0), ".世..");
+
+        draw(&mut canvas, 1, 0, "a");
+        assert_eq!(row(&canvas, 0), ".a ..");
+    }
+
+    #[test]
+    fn drawing_over_the_right_half_of_a_wide_character_blanks_the_left() {
+        let mut canvas = Canvas::new(5, 1);
+        draw(&mut canvas, 1, 0, "世");
+        draw(&mut canvas, 2, 0, "b");
+        assert_eq!(row(&canvas, 0), ". b..");
+    }
+
+    #[test]
+    fn a_wide_character_that_does_not_fit_leaves_a_gap() {
+        let mut canvas = Canvas::new(4, 1);
+        draw(&mut canvas, 3, 0, "世");
+        assert_eq!(row(&canvas, 0), "... ");
+
+        let mut canvas = Canvas::new(4, 1);
+        canvas.push_clip(Rect::new(0, 0, 2, 1));
+        draw(&mut canvas, 1, 0, "世");
+        assert_eq!(row(&canvas, 0), ". ..");
+    }
+
+    #[test]
+    fn combining_marks_go_on_the_wide_character_before_them() {
+        let mut canvas = Canvas::new(4, 1);
+        draw(&mut canvas, 0, 0, "世\u{301}x");
+        assert_eq!(canvas.cell(0, 0).unwrap().combining, "\u{301}");
+        assert_eq!(row(&canvas, 0), "世\u{301}x.");
+    }
+
+    #[test]
+    fn halves_split_up_by_compositing_are_blanked() {
+        let mut below = Canvas::new(4, 1);
+        draw(&mut below, 0, 0, "世");
+        let mut above = Canvas::new(4, 1);
+        draw(&mut above, 1, 0, "c");
+
+        below.draw_canvas(&above, 0, 0, 1.0);
+        below.repair_wide_characters();
+        assert_eq!(row(&below, 0), " c..");
+    }
+
+    #[test]
+    fn text_off_either_side_is_cropped() {
+        let mut canvas = Canvas::new(5, 1);
+        draw(&mut canvas, -2, 0, "hello");
+        assert_eq!(row(&canvas, 0), "llo..");
+
+        let mut canvas = Canvas::new(5, 1);
+        draw(&mut canvas, 3, 0, "hello");
+        assert_eq!(row(&canvas, 0), "...he");
+    }
+
+    #[test]
+    fn art_off_the_top_and_bottom_is_cropped() {
+        let art = "ab\ncd\nef";
+
+        let mut canvas = Canvas::new(3, 3);
+        draw(&mut canvas, -1, -1, art);
+        assert_eq!((row(&canvas, 0), row(&canvas, 1), row(&canvas, 2)), ("d..".to_string(), "f..".to_string(), "...".to_string()));
+
+        let mut canvas = Canvas::new(3, 3);
+        draw(&mut canvas, 2, 1, art);
+        assert_eq!((row(&canvas, 0), row(&canvas, 1), row(&canvas, 2)), ("...".to_string(), "..a".to_string(), "..c".to_string()));
+    }
+
+    #[test]
+    fn nested_clips_only_allow_what_both_allow() {
+        let mut canvas = Canvas::new(6, 1);
+        canvas.push_clip(Rect::new(1, 0, 3, 1));
+        canvas.push_clip(Rect::new(2, -5, 10, 10));
+        assert_eq!(canvas.clip(), Rect::new(2, 0, 2, 1));
+
+        draw(&mut canvas, 0, 0, "abcdef");
+        assert_eq!(row(&canvas, 0), "..cd..");
+
+        canvas.pop_clip();
+        draw(&mut canvas, 0, 0, "ABCDEF");
+        assert_eq!(row(&canvas, 0), ".BCD..");
+
+        canvas.pop_clip();
+        assert_eq!(canvas.clip(), canvas.bounds());
+    }
+}
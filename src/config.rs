@@ -0,0 +1,192 @@
+use std::path::PathBuf;
+use std::str::FromStr;
+use chrono::NaiveDate;
+use crate::calendar::parse_date;
+use crate::days;
+use crate::drawing::CALENDAR_DAYS;
+use crate::frame_limiter::DEFAULT_FPS;
+use crate::screen::{ScalePolicy, MAX_CANVAS_SIZE};
+use crate::theme::{self, Theme};
+use crate::transition::TransitionEffect;
+
+pub const USAGE: &str = "\
+Usage: my_project [options]
+
+Options:
+  --day N            Open door N straight away, even if it's still locked
+  --seed N           Seed the random number generator
+  --fps N            Frames per second, 60 by default and 0 for no limit
+  --debug            Show the debug overlay
+  --no-debug         Hide the debug overlay
+  --theme NAME       Color theme: classic, frost or mono
+  --scale MODE       center: keep to 200x70 in the middle of bigger terminals (default)
+                     fill: use the whole terminal
+  --transition NAME  Use one transition everywhere: radial, wipe-left, wipe-right,
+                     wipe-up, wipe-down, dissolve, snow or crossfade
+  --save PATH        Keep progress in this file instead of the data directory
+  --headless N       Run N frames without a terminal and print the last frame
+  --now YYYY-MM-DD   Unlock doors as if it were this date
+  --unlock-all       Unlock every door
+  --record PATH      Record the session's input to a file
+  --replay PATH      Replay a recorded session
+  --help             Show this message";
+
+pub struct Config {
+    pub record: Option<PathBuf>,
+    pub replay: Option<PathBuf>,
+    // Pretend it's this date instead of today, to preview the calendar out of season
+    pub now: Option<NaiveDate>,
+    pub unlock_all: bool,
+    pub day: Option<usize>,
+    pub seed: Option<u64>,
+    pub fps: u32,
+    pub debug: bool,
+    pub theme: Theme,
+    pub scale: ScalePolicy,
+    pub transition: Option<TransitionEffect>,
+    pub save: Option<PathBuf>,
+    // Number of frames to run without a terminal
+    pub headless: Option<usize>,
+    pub help: bool,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Config {
+            record: None,
+            replay: None,
+            now: None,
+            unlock_all: false,
+            day: None,
+            seed: None,
+            fps: DEFAULT_FPS,
+            // The overlay is for development, so only debug builds show it unless asked
+            debug: cfg!(debug_assertions),
+            theme: theme::CLASSIC,
+            scale: ScalePolicy::Center { width: MAX_CANVAS_SIZE.0, height: MAX_CANVAS_SIZE.1 },
+            transition: None,
+            save: None,
+            headless: None,
+            help: false,
+        }
+    }
+}
+
+impl Config {
+    pub fn from_args(args: impl Iterator<Item = String>) -> Result<Config, String> {
+        let mut config = Config::default();
+        let mut args = args.skip(1);
+
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--record" => config.record = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--replay" => config.replay = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--now" => config.now = Some(parse_date(&value(&arg, args.next())?)?),
+                "--unlock-all" => config.unlock_all = true,
+                "--day" => config.day = Some(number(&arg, args.next())?),
+                "--seed" => config.seed = Some(number(&arg, args.next())?),
+                "--fps" => config.fps = number(&arg, args.next())?,
+                "--debug" => config.debug = true,
+                "--no-debug" => config.debug = false,
+                "--theme" => {
+                    let name = value(&arg, args.next())?;
+                    config.theme = theme::find(&name).ok_or_else(|| format!("unknown theme '{}'", name))?;
+                }
+                "--scale" => {
+                    let name = value(&arg, args.next())?;
+                    config.scale = ScalePolicy::find(&name).ok_or_else(|| format!("unknown scale mode '{}'", name))?;
+                }
+                "--transition" => {
+                    let name = value(&arg, args.next())?;
+                    config.transition = Some(TransitionEffect::find(&name).ok_or_else(|| format!("unknown transition '{}'", name))?);
+                }
+                "--save" => config.save = Some(PathBuf::from(value(&arg, args.next())?)),
+                "--headless" => config.headless = Some(number(&arg, args.next())?),
+                "--help" | "-h" => config.help = true,
+                _ => return Err(format!("unknown argument '{}'", arg)),
+            }
+        }
+
+        if config.record.is_some() && config.replay.is_some() {
+            return Err("--record and --replay can't be used together".to_string());
+        }
+
+        if config.record.is_some() && config.headless.is_some() {
+            return Err("--record and --headless can't be used together".to_string());
+        }
+
+        if config.seed.is_some() && config.replay.is_some() {
+            return Err("--seed can't be used with --replay, the recording has its own".to_string());
+        }
+
+        if let Some(day) = config.day {
+            if day == 0 || day > CALENDAR_DAYS {
+                return Err(format!("--day must be between 1 and {}", CALENDAR_DAYS));
+            }
+            if days::find(day).is_none() {
+                return Err(format!("day {} has nothing behind its door yet", day));
+            }
+        }
+
+        Ok(config)
+    }
+}
+
+fn value(flag: &str, value: Option<String>) -> Result<String, String> {
+    value.ok_or_else(|| format!("{} needs a value", flag))
+}
+
+fn number<T: FromStr>(flag: &str, value: Option<String>) -> Result<T, String> {
+    let text = self::value(flag, value)?;
+    text.parse().map_err(|_| format!("{} needs a number, got '{}'", flag, text))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Config;
+
+    // The program name comes first, like in std::env::args
+    fn parse(args: &str) -> Result<Config, String> {
+        Config::from_args(std::iter::once("my_project").chain(args.split_whitespace()).map(String::from))
+    }
+
+    #[test]
+    fn valid_flags_are_read() {
+        let config = parse("--day 2 --seed 7 --fps 30 --unlock-all").unwrap();
+        assert_eq!((config.day, config.seed, config.fps), (Some(2), Some(7), 30));
+        assert!(config.unlock_all);
+    }
+
+    #[test]
+    fn unknown_flags_are_refused() {
+        assert_eq!(parse("--colour red").err().unwrap(), "unknown argument '--colour'");
+    }
+
+    #[test]
+    fn a_flag_needs_its_value() {
+        assert_eq!(parse("--seed").err().unwrap(), "--seed needs a value");
+    }
+
+    #[test]
+    fn numbers_have_to_be_numbers() {
+        assert_eq!(parse("--fps fast").err().unwrap(), "--fps needs a number, got 'fast'");
+    }
+
+    #[test]
+    fn the_day_has_to_be_a_door_with_something_behind_it() {
+        assert_eq!(parse("--day 0").err().unwrap(), "--day must be between 1 and 24");
+        assert_eq!(parse("--day 25").err().unwrap(), "--day must be between 1 and 24");
+        assert_eq!(parse("--day 5").err().unwrap(), "day 5 has nothing behind its door yet");
+    }
+
+    #[test]
+    fn recording_rules_out_replaying_and_headless_runs() {
+        assert_eq!(parse("--record a --replay b").err().unwrap(), "--record and --replay can't be used together");
+        assert_eq!(parse("--record a --headless 10").err().unwrap(), "--record and --headless can't be used together");
+    }
+
+    #[test]
+    fn a_replay_brings_its_own_seed() {
+        assert_eq!(parse("--seed 1 --replay b").err().unwrap(), "--seed can't be used with --replay, the recording has its own");
+    }
+}
@@ -0,0 +1,54 @@
+use chrono::NaiveDate;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
+use crate::calendar::{door_state, DoorState};
+use crate::progress::Progress;
+use crate::transition::TransitionEffect;
+
+// Shared app state handed to every State alongside the Screen and Input
+pub struct Context {
+    // All randomness goes through here so recorded sessions replay exactly
+    pub rng: StdRng,
+    // The date doors are unlocked against, the local date unless overridden
+    pub today: NaiveDate,
+    pub unlock_all: bool,
+    pub progress: Progress,
+    // Used for every transition instead of the one each state asks for
+    pub transition: Option<TransitionEffect>,
+}
+
+impl Context {
+    pub fn new(seed: u64, today: NaiveDate, unlock_all: bool, progress: Progress) -> Context {
+        Context {
+            rng: StdRng::seed_from_u64(seed),
+            today,
+            unlock_all,
+            progress,
+            transition: None,
+        }
+    }
+
+    pub fn door_state(&self, day: usize) -> DoorState {
+        if self.unlock_all {
+            return DoorState::Open;
+        }
+        door_state(day, self.today)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calendar::DoorState;
+    use crate::progress::Progress;
+    use crate::testing::date;
+    use super::Context;
+
+    #[test]
+    fn unlock_all_opens_doors_out_of_season() {
+        let locked = Context::new(1, date(2024, 11, 1), false, Progress::in_memory());
+        assert_ne!(locked.door_state(24), DoorState::Open);
+
+        let unlocked = Context::new(1, date(2024, 11, 1), true, Progress::in_memory());
+        assert!((1..=24).all(|day| unlocked.door_state(day) == DoorState::Open));
+    }
+}
@@ -0,0 +1,56 @@
+use crate::state_machine::State;
+pub use crate::states::DAYS;
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum DayKind {
+    // Something to solve, the door counts as done once it's solved
+    Puzzle,
+    // Something to look at
+    Showcase,
+}
+
+impl DayKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            DayKind::Puzzle => "Oppgave",
+            DayKind::Showcase => "Utstilling",
+        }
+    }
+}
+
+pub struct DayEntry {
+    pub day: usize,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub kind: DayKind,
+    pub create: fn() -> Box<dyn State>,
+}
+
+// Declares the day modules and lists the DAY entry each of them describes itself with,
+// so the list can't get out of step with the modules
+macro_rules! register_days {
+    ($($module:ident),* $(,)?) => {
+        $(pub mod $module;)*
+
+        // Every door with content behind it
+        pub const DAYS: &[$crate::days::DayEntry] = &[$($module::DAY),*];
+    };
+}
+pub(crate) use register_days;
+
+pub fn find(day: usize) -> Option<&'static DayEntry> {
+    DAYS.iter().find(|entry| entry.day == day)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::drawing::CALENDAR_DAYS;
+    use super::DAYS;
+
+    #[test]
+    fn days_are_listed_once_in_order() {
+        let days: Vec<usize> = DAYS.iter().map(|entry| entry.day).collect();
+        assert!(days.windows(2).all(|pair| pair[0] < pair[1]), "{:?}", days);
+        assert!(days.iter().all(|day| (1..=CALENDAR_DAYS).contains(day)), "{:?}", days);
+    }
+}
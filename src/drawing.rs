@@ -1,267 +1,250 @@
 extern crate crossterm;
 
+use crossterm::event::KeyCode;
 use crossterm::style;
-use crate::input::{Input, InputEvent, MouseButton};
+use crossterm::style::Attribute;
+use crate::input::{Input, MouseButton};
 use crate::screen::Screen;
+use crate::calendar::DoorState;
+use crate::days::{self, DayKind};
+use crate::progress::Progress;
+use crate::theme::Theme;
+use crate::widgets::button::Button;
+use crate::widgets::grid::Grid;
+use crate::widgets::label::Label;
+use crate::widgets::layout::{HBox, VBox};
+use crate::widgets::panel::Panel;
+use unicode_width::UnicodeWidthChar;
 
 pub fn draw_debug_info(
     screen: &mut Screen,
     input: &mut Input,
     dt: f64,
 ) {
-    let fps_str = format!("FPS: {:.0}", 1.0 / dt);
-    for (i, c) in fps_str.chars().enumerate() {
-        screen.set_cell(i as u16, 0, c, style::Color::White);
-    }
-
-    let mouse_pos_str = format!("Mouse: ({}, {})", input.mouse_position().0, input.mouse_position().1);
-    for (i, c) in mouse_pos_str.chars().enumerate() {
-        screen.set_cell(i as u16, 1, c, style::Color::White);
-    }
+    let fps_str = format!("FPS: {:.0}  Bytes: {}", 1.0 / dt, screen.bytes_written());
+    draw_text(screen, 0, 0, &fps_str, screen.theme().text);
+
+    let mouse_pos_str = format!(
+        "Mouse: ({}, {})  Delta: {:?}  Scroll: {:?}",
+        input.mouse_position().0,
+        input.mouse_position().1,
+        input.mouse_delta(),
+        input.scroll_delta()
+    );
+    draw_text(screen, 0, 1, &mouse_pos_str, screen.theme().text);
 
     let mouse_buttons = vec![
-        (input.is_mouse_down(MouseButton::Left), "Left"),
-        (input.is_mouse_down(MouseButton::Middle), "Middle"),
-        (input.is_mouse_down(MouseButton::Right), "Right"),
+        (MouseButton::Left, "Left"),
+        (MouseButton::Middle, "Middle"),
+        (MouseButton::Right, "Right"),
     ];
 
-    for (i, (is_down, button)) in mouse_buttons.iter().enumerate() {
-        let mouse_down_str = format!("Mouse {}: {}", button, is_down);
-        for (j, c) in mouse_down_str.chars().enumerate() {
-            screen.set_cell(j as u16, (i + 2) as u16, c, style::Color::White);
+    for (i, (button, name)) in mouse_buttons.into_iter().enumerate() {
+        let mut mouse_down_str = format!("Mouse {}: {}", name, input.is_mouse_down(button));
+        if let Some(drag) = input.drag(button) {
+            mouse_down_str += &format!("  Drag: {:?} -> {:?}", drag.start, drag.end);
         }
+        if input.is_double_click(button) {
+            mouse_down_str += "  Double click";
+        }
+
+        draw_text(screen, 0, i as i32 + 2, &mouse_down_str, screen.theme().text);
     }
 
-    // draw all keys that are pressed
-    let keymap = input.keymap();
-    for (i, (key, input_event)) in keymap.iter().enumerate() {
-        match input_event {
-            Some(InputEvent::Down) => {
-                let key_str = format!("Key {}: Down", key);
-                for (j, c) in key_str.chars().enumerate() {
-                    screen.set_cell(j as u16, (i + 5) as u16, c, style::Color::White);
-                }
-            }
-            Some(InputEvent::Up) => {
-                let key_str = format!("Key {}: Up", key);
-                for (j, c) in key_str.chars().enumerate() {
-                    screen.set_cell(j as u16, (i + 5) as u16, c, style::Color::White);
-                }
-            }
-            None => {}
+    let keyboard_str = format!("Keyboard: {}  Modifiers: {}", input.keyboard_mode().name(), input.modifiers());
+    draw_text(screen, 0, 5, &keyboard_str, screen.theme().text);
+
+    // Every key that's doing something this frame
+    let keys: Vec<KeyCode> = input.keymap().keys().copied().collect();
+    for (i, key) in keys.into_iter().enumerate() {
+        let states: Vec<&str> = [
+            (input.is_key_pressed(key), "Pressed"),
+            (input.is_key_held(key), "Held"),
+            (input.is_key_repeated(key), "Repeated"),
+            (input.is_key_released(key), "Released"),
+        ]
+        .into_iter()
+        .filter_map(|(active, name)| active.then_some(name))
+        .collect();
+
+        if !states.is_empty() {
+            draw_text(screen, 0, i as i32 + 6, &format!("Key {}: {}", key, states.join(", ")), screen.theme().text);
         }
     }
 }
 
+// Shown instead of the states while the terminal is smaller than they need
+pub fn draw_too_small(screen: &mut Screen, (min_width, min_height): (i32, i32)) {
+    let (width, height) = screen.terminal_size();
+    let lines = [
+        "Terminalen er for liten".to_string(),
+        format!("Gjør vinduet minst {}x{}", min_width, min_height),
+        format!("Nå er det {}x{}", width, height),
+    ];
+
+    let y = (screen.height() - lines.len() as i32) / 2;
+    for (i, line) in lines.iter().enumerate() {
+        let x = (screen.width() - text_width(line)).max(0) / 2;
+        let color = if i == 0 { screen.theme().star } else { screen.theme().text };
+        draw_text(screen, x, y + i as i32, line, color);
+    }
+}
+
 pub fn draw_ground(screen: &mut Screen) {
     for i in 0..screen.width() {
-        screen.set_cell(i, screen.height() - 1, '█', style::Color::White);
+        screen.set_cell(i, screen.height() - 1, '█', screen.theme().text);
     }
 }
 
-pub fn draw_ascii(screen: &mut Screen, ascii: &str, x: u16, y: u16) {
-    let lines = ascii.lines();
+// Columns a character takes up on screen, two for wide ones and none for combining marks
+pub fn char_width(c: char) -> i32 {
+    c.width().unwrap_or(0) as i32
+}
 
-    for (i, line) in lines.enumerate() {
-        for (j, c) in line.chars().enumerate() {
-            if c == ' ' {
-                continue;
-            }
+pub fn text_width(text: &str) -> i32 {
+    text.chars().map(char_width).sum()
+}
 
-            screen.set_cell(x + j as u16, y + i as u16, c, style::Color::White);
-        }
+// Draws a single line of text starting at x and returns how many columns it took up
+pub fn draw_text(screen: &mut Screen, x: i32, y: i32, text: &str, color: style::Color) -> i32 {
+    let mut column = x;
+    for c in text.chars() {
+        screen.set_cell(column, y, c, color);
+        column += char_width(c);
     }
+    column - x
 }
 
-pub fn draw_question(screen: &mut Screen, mouse_position: (u16, u16), mouse_down: bool,
-    question: &str,
-    correct_answer: &str,
-    wrong_answers: &[&str],
-    correct_answer_position: usize,
-    on_correct_answer: &mut dyn FnMut(),
-) {
-    let width = screen.width();
-    let height = screen.height();
-
-    draw_text_box(screen, width, height, question, 0, -5, (0, 0), false);
-
-    let total_answers = wrong_answers.len() + 1;
-
-    let mut incorrect_is_hovered: Vec<bool> = vec![];
-    let mut correct_answer_hovered: bool = false;
-
-
-    let delta_offset: i16 = 20;
-    let minimum_offset: i16 =
-        (((total_answers - 1) as f32 / 2.0) * -delta_offset as f32) as i16;
-
-    let mut wrong_answer_iterator: usize = 0;
-
-    for i in 0..total_answers {
-        let x_offset = minimum_offset + delta_offset * i as i16;
-        if i == correct_answer_position {
-            correct_answer_hovered = draw_text_box(
-                screen,
-                width,
-                height,
-                correct_answer,
-                x_offset,
-                0,
-                mouse_position,
-                mouse_down,
-            );
-        } else {
-            incorrect_is_hovered.push(draw_text_box(
-                screen,
-                width,
-                height,
-                wrong_answers[wrong_answer_iterator],
-                x_offset,
-                0,
-                mouse_position,
-                mouse_down,
-            ));
-            wrong_answer_iterator += 1;
-        }
-    }
+// Spaces are see-through, so art can be drawn over a scene without blanking around it
+pub fn draw_ascii(screen: &mut Screen, ascii: &str, x: i32, y: i32) {
+    let lines = ascii.lines();
 
-    if mouse_down {
-        let any_incorrect_is_hovered = incorrect_is_hovered.into_iter().any(|x| x);
-        if correct_answer_hovered && !any_incorrect_is_hovered {
-            draw_text_box(
-                screen,
-                width,
-                height,
-                "Correct!",
-                0,
-                5,
-                (0, 0),
-                false,
-            );
-            on_correct_answer();
-        } else if any_incorrect_is_hovered {
-            draw_text_box(screen, width, height, "Wrong!", 0, 5, (0, 0), false);
+    for (i, line) in lines.enumerate() {
+        let mut column = x;
+        for word in line.split(' ') {
+            column += draw_text(screen, column, y + i as i32, word, screen.theme().text) + 1;
         }
     }
 }
 
-pub fn draw_text_box(screen: &mut Screen, width: u16, height: u16, q: &str, x_offset: i16, y_offset: i16, mouse_position: (u16, u16), mouse_down: bool) -> bool {
-    let question = q;
-    let x_origin = ((width as i16 - question.len() as i16) / 2 + x_offset) as u16;
-    let y_origin = (height as i16 / 2 + y_offset) as u16;
-
-    let fancy_top_border = "╭".to_string() + &"─".repeat(question.len() + 4) + "╮";
-    let fancy_bottom_border = "╰".to_string() + &"─".repeat(question.len() + 4) + "╯";
-
-    let mut color = style::Color::White;
-
-    let mut is_hovered = false;
-    if mouse_position.0 >= x_origin - 3 &&
-        mouse_position.0 <= x_origin + question.len() as u16 + 2 &&
-        mouse_position.1 >= y_origin - 1 &&
-        mouse_position.1 <= y_origin + 1 {
-
-        if mouse_down {
-            color = style::Color::Rgb {
-                r: 0,
-                g: 255,
-                b: 0,
-            };
-        } else {
-            color = style::Color::Rgb {
-                r: 255,
-                g: 255,
-                b: 0,
-            };
-        }
-        is_hovered = true;
-    }
+// The question in a box, a button per answer and a line saying whether the last answer was right.
+// Answers are identified by their index.
+pub fn question_widgets(question: &str, answers: &[&str], answered_correctly: Option<bool>) -> VBox {
+    let answer_buttons = answers
+        .iter()
+        .enumerate()
+        .fold(HBox::new().spacing(4), |row, (i, answer)| row.child(Button::new(i, *answer)));
+
+    // Always there, so the rest doesn't jump once there is something to say
+    let feedback = match answered_correctly {
+        Some(true) => Label::new("Correct!").attributes(Attribute::Bold.into()),
+        Some(false) => Label::new("Wrong!"),
+        None => Label::new(""),
+    };
+
+    VBox::new()
+        .spacing(2)
+        .child(Panel::new(Label::new(question)).padding(2, 0))
+        .child(answer_buttons)
+        .child(feedback)
+}
 
-    for i in 0..3 {
-        let line = " ".repeat(question.len() + 4);
-        for (j, c) in line.chars().enumerate() {
-            screen.set_cell(x_origin - 2 + j as u16, y_origin + i as u16, c, style::Color::White);
-        }
-    }
+pub const CALENDAR_DAYS: usize = 24;
+pub const CALENDAR_COLUMNS: usize = 6;
 
-    if y_origin > 0 {
-        let line = &fancy_top_border;
-        for (j, c) in line.chars().enumerate() {
-            screen.set_cell(x_origin - 3 + j as u16, y_origin - 1, c, color);
-        }
+// Puzzles are done when solved, showcases once they have been seen
+fn is_day_done(progress: &Progress, day: usize, kind: DayKind) -> bool {
+    match kind {
+        DayKind::Puzzle => progress.is_completed(day),
+        DayKind::Showcase => progress.is_opened(day),
     }
+}
 
-    if y_origin < height - 1
-    {
-        let line = &fancy_bottom_border;
-        for (j, c) in line.chars().enumerate() {
-            screen.set_cell(x_origin - 3 + j as u16, y_origin + 1, c, color);
-        }
+fn best_results_text(score: Option<u32>, moves: Option<u32>, time: Option<f64>) -> String {
+    let mut results = Vec::new();
+    if let Some(score) = score {
+        results.push(format!("{} poeng", score));
     }
-
-    if x_origin > 3 {
-        screen.set_cell(x_origin - 3, y_origin, '│', color);
+    if let Some(moves) = moves {
+        results.push(format!("{} trekk", moves));
     }
-
-    if x_origin + question.len() as u16 + 2 < width {
-        screen.set_cell(x_origin + question.len() as u16 + 2, y_origin, '│', color);
+    if let Some(time) = time {
+        let seconds = time.round() as u64;
+        results.push(format!("{}:{:02}", seconds / 60, seconds % 60));
     }
 
-    for (i, c) in question.chars().enumerate() {
-        screen.set_cell(x_origin + i as u16, y_origin, c, color);
+    if results.is_empty() {
+        return String::new();
     }
+    format!(" | Beste: {}", results.join(", "))
+}
 
-    is_hovered
+pub fn come_back_text(days_left: i64) -> String {
+    if days_left == 1 {
+        "kom tilbake i morgen".to_string()
+    } else {
+        format!("kom tilbake om {} dager", days_left)
+    }
 }
 
-pub fn draw_calendar(
-    screen: &mut Screen,
-    mouse_position: (u16, u16),
-    mouse_down: bool,
-) -> Option<usize> {
-    let total_days:usize = 24;
-    let columns:i16 = 6;
-    let rows:i16 = (total_days as f32 / columns as f32).ceil() as i16;
-
-    let box_width = 6;
-
-    let padding = 4;
-    let x_start:i16 = -(columns * box_width) / 2 - box_width;
-    let y_start:i16 = -((rows - 1) * padding) / 2;
-
-    let x_step = box_width + padding;
-    let y_step = padding;
-
-    let mut day_is_hovered = vec![false; total_days];
-
-    for i in 0..total_days {
-        let x_offset = x_start + (i as i16 % columns) * x_step;
-        let y_offset = y_start + (i as i16 / columns) * y_step;
-
-        let day_text = if i < 9 {
-            format!("0{}", i + 1)
-        } else {
-            (i + 1).to_string()
-        };
-
-        day_is_hovered[i] = draw_text_box(
-            screen,
-            screen.width(),
-            screen.height(),
-            &day_text,
-            x_offset,
-            y_offset,
-            mouse_position,
-            mouse_down,
-        );
+// The doors in a grid, identified by their day, with a line describing described_day underneath
+pub fn calendar_widgets(theme: &Theme, described_day: Option<usize>, doors: &[DoorState], progress: &Progress) -> VBox {
+    let mut grid = Grid::new(CALENDAR_COLUMNS).gap(2, 1);
+
+    for day in 1..=CALENDAR_DAYS {
+        let mut door = Button::new(day, format!("{:02}", day));
+
+        // Doors with nothing behind them yet are drawn faded, locked ones in a cold blue
+        let entry = days::find(day);
+        let is_done = entry.is_some_and(|entry| is_day_done(progress, entry.day, entry.kind));
+        if entry.is_none() {
+            door = door.color(style::Color::DarkGrey).attributes(Attribute::Dim.into());
+        } else if doors.get(day - 1).is_some_and(|door| *door != DoorState::Open) {
+            door = door.color(theme.locked);
+        } else if is_done {
+            // A star in the top border of every finished door
+            door = door.color(theme.done).mark('*', theme.star);
+        }
+
+        grid = grid.child(door);
     }
 
-    if mouse_down {
-        for (i, is_hovered) in day_is_hovered.iter().enumerate() {
-            if *is_hovered {
-                return Some(i+1);
+    let description = described_day.map_or_else(String::new, |day| door_description(day, doors, progress));
+    VBox::new()
+        .spacing(2)
+        .child(grid)
+        .child(Label::new(description))
+}
+
+fn door_description(day: usize, doors: &[DoorState], progress: &Progress) -> String {
+    match (days::find(day), doors.get(day - 1).copied().unwrap_or(DoorState::Open)) {
+        (Some(_), DoorState::Locked { days_left }) => format!("{}: Låst, {}", day, come_back_text(days_left)),
+        (Some(entry), DoorState::Open) => {
+            let mut description = format!("{}: {} ({}) - {}", day, entry.title, entry.kind.name(), entry.description);
+            if let Some(best) = progress.day(day).filter(|day| day.completed) {
+                description += &best_results_text(best.best_score, best.best_moves, best.best_time);
             }
+            description
         }
+        (None, _) => format!("{}: Kommer snart", day),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backend::MemoryBackend;
+    use crate::screen::Screen;
+    use super::draw_ascii;
+
+    #[test]
+    fn art_partly_off_screen_draws_the_part_that_is_on_it() {
+        let mut screen = Screen::new(Box::new(MemoryBackend::new()), (4, 2));
+        draw_ascii(&mut screen, "abc\ndef\nghi", -1, -1);
+        draw_ascii(&mut screen, "xyz", 2, 1);
+
+        let rune = |x, y| screen.cell(x, y).map(|cell| cell.rune);
+        assert_eq!((rune(0, 0), rune(1, 0)), (Some('e'), Some('f')));
+        assert_eq!((rune(0, 1), rune(1, 1)), (Some('h'), Some('i')));
+        assert_eq!((rune(2, 1), rune(3, 1)), (Some('x'), Some('y')));
     }
-    None
 }
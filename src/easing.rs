@@ -0,0 +1,60 @@
+// Maps linear progress from 0 to 1 onto a curve
+#[derive(Clone, Copy, PartialEq)]
+pub enum Easing {
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicOut,
+    SineInOut,
+}
+
+impl Easing {
+    pub fn apply(&self, t: f64) -> f64 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut => {
+                if t < 0.5 {
+                    2.0 * t * t
+                } else {
+                    1.0 - (-2.0 * t + 2.0).powi(2) / 2.0
+                }
+            }
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::SineInOut => -((std::f64::consts::PI * t).cos() - 1.0) / 2.0,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Easing;
+
+    const ALL: [Easing; 6] = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicOut,
+        Easing::SineInOut,
+    ];
+
+    #[test]
+    fn every_curve_starts_at_0_and_ends_at_1() {
+        for easing in ALL {
+            assert!(easing.apply(0.0).abs() < 1e-9);
+            assert!((easing.apply(1.0) - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn progress_outside_0_to_1_is_clamped() {
+        for easing in ALL {
+            assert!(easing.apply(-0.5).abs() < 1e-9);
+            assert!((easing.apply(1.5) - 1.0).abs() < 1e-9);
+        }
+    }
+}
@@ -0,0 +1,178 @@
+use std::collections::VecDeque;
+use std::io::Error;
+use std::time::{Duration, Instant};
+use crossterm::event;
+use crossterm::event::Event;
+#[cfg(test)]
+use crossterm::event::{KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
+
+pub trait EventSource {
+    // Starts a new frame and returns the time in seconds since the previous one
+    fn begin_frame(&mut self) -> Result<f64, Error>;
+
+    // Returns the next event that is already waiting, without blocking
+    fn poll_event(&mut self) -> Result<Option<Event>, Error>;
+
+    // True once a finite source has nothing more to play back
+    fn is_finished(&self) -> bool {
+        false
+    }
+}
+
+pub struct CrosstermEvents {
+    previous_time: Instant,
+}
+
+impl CrosstermEvents {
+    pub fn new() -> CrosstermEvents {
+        CrosstermEvents {
+            previous_time: Instant::now(),
+        }
+    }
+}
+
+impl EventSource for CrosstermEvents {
+    fn begin_frame(&mut self) -> Result<f64, Error> {
+        let new_time = Instant::now();
+        let dt = new_time.duration_since(self.previous_time).as_nanos() as f64 / 1_000_000_000.0;
+        self.previous_time = new_time;
+        Ok(dt)
+    }
+
+    fn poll_event(&mut self) -> Result<Option<Event>, Error> {
+        if event::poll(Duration::from_millis(0))? {
+            return Ok(Some(event::read()?));
+        }
+
+        Ok(None)
+    }
+}
+
+pub struct ScriptedFrame {
+    pub dt: f64,
+    pub events: Vec<Event>,
+}
+
+// Plays back pre-built frames, each with its own dt and the events that arrive during it
+pub struct ScriptedEvents {
+    frames: VecDeque<ScriptedFrame>,
+    current: VecDeque<Event>,
+    exhausted: bool,
+}
+
+impl ScriptedEvents {
+    pub fn new() -> ScriptedEvents {
+        ScriptedEvents {
+            frames: VecDeque::new(),
+            current: VecDeque::new(),
+            exhausted: false,
+        }
+    }
+
+    #[cfg(test)]
+    pub fn frame(mut self, dt: f64, events: Vec<Event>) -> ScriptedEvents {
+        self.push_frame(dt, events);
+        self
+    }
+
+    pub fn idle(mut self, frames: usize, dt: f64) -> ScriptedEvents {
+        for _ in 0..frames {
+            self.push_frame(dt, vec![]);
+        }
+        self
+    }
+
+    pub fn push_frame(&mut self, dt: f64, events: Vec<Event>) {
+        self.frames.push_back(ScriptedFrame { dt, events });
+    }
+
+}
+
+impl EventSource for ScriptedEvents {
+    fn begin_frame(&mut self) -> Result<f64, Error> {
+        match self.frames.pop_front() {
+            Some(frame) => {
+                self.current.extend(frame.events);
+                Ok(frame.dt)
+            }
+            None => {
+                self.exhausted = true;
+                Ok(0.0)
+            }
+        }
+    }
+
+    fn poll_event(&mut self) -> Result<Option<Event>, Error> {
+        Ok(self.current.pop_front())
+    }
+
+    // Only after a frame was asked for past the end, so the last frame still gets played
+    fn is_finished(&self) -> bool {
+        self.exhausted
+    }
+}
+
+// For building scripts in tests
+#[cfg(test)]
+pub fn key_press(code: KeyCode) -> Event {
+    Event::Key(KeyEvent::new_with_kind(code, KeyModifiers::NONE, KeyEventKind::Press))
+}
+
+#[cfg(test)]
+pub fn key_release(code: KeyCode) -> Event {
+    Event::Key(KeyEvent::new_with_kind(code, KeyModifiers::NONE, KeyEventKind::Release))
+}
+
+#[cfg(test)]
+pub fn mouse_down(button: MouseButton, x: u16, y: u16) -> Event {
+    mouse_event(MouseEventKind::Down(button), x, y)
+}
+
+#[cfg(test)]
+pub fn mouse_up(button: MouseButton, x: u16, y: u16) -> Event {
+    mouse_event(MouseEventKind::Up(button), x, y)
+}
+
+#[cfg(test)]
+pub fn mouse_move(x: u16, y: u16) -> Event {
+    mouse_event(MouseEventKind::Moved, x, y)
+}
+
+#[cfg(test)]
+pub fn resize(width: u16, height: u16) -> Event {
+    Event::Resize(width, height)
+}
+
+#[cfg(test)]
+fn mouse_event(kind: MouseEventKind, x: u16, y: u16) -> Event {
+    Event::Mouse(MouseEvent {
+        kind,
+        column: x,
+        row: y,
+        modifiers: KeyModifiers::NONE,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use super::{key_press, EventSource, ScriptedEvents};
+
+    #[test]
+    fn scripted_frames_play_back_in_order() {
+        let mut events = ScriptedEvents::new()
+            .frame(0.5, vec![key_press(KeyCode::Enter)])
+            .idle(1, 0.25);
+
+        assert_eq!(events.begin_frame().unwrap(), 0.5);
+        assert_eq!(events.poll_event().unwrap(), Some(key_press(KeyCode::Enter)));
+        assert_eq!(events.poll_event().unwrap(), None);
+
+        assert_eq!(events.begin_frame().unwrap(), 0.25);
+        assert_eq!(events.poll_event().unwrap(), None);
+        assert!(!events.is_finished());
+
+        assert_eq!(events.begin_frame().unwrap(), 0.0);
+        assert!(events.is_finished());
+    }
+}
@@ -0,0 +1,64 @@
+use std::time::{Duration, Instant};
+use crate::input::Input;
+
+pub const DEFAULT_FPS: u32 = 60;
+
+// Nothing needs to be smooth while another window has focus
+const UNFOCUSED_FRAME_TIME: Duration = Duration::from_millis(250);
+
+// Paces the main loop by sleeping for whatever is left of each frame
+pub struct FrameLimiter {
+    frame_time: Option<Duration>,
+    frame_start: Instant,
+}
+
+impl FrameLimiter {
+    // 0 fps means no limit
+    pub fn new(fps: u32) -> FrameLimiter {
+        FrameLimiter {
+            frame_time: (fps > 0).then(|| Duration::from_secs_f64(1.0 / fps as f64)),
+            frame_start: Instant::now(),
+        }
+    }
+
+    pub fn begin_frame(&mut self) {
+        self.frame_start = Instant::now();
+    }
+
+    // Sleeps through input too, a stream of mouse moves would otherwise run frames back to back.
+    // Whatever arrived in the meantime is read at the start of the next frame.
+    pub fn wait(&self, input: &Input) {
+        let frame_time = if input.has_focus() {
+            self.frame_time
+        } else {
+            Some(UNFOCUSED_FRAME_TIME)
+        };
+
+        if let Some(remaining) = frame_time.and_then(|time| time.checked_sub(self.frame_start.elapsed())) {
+            std::thread::sleep(remaining);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+    use crate::event_source::{mouse_move, ScriptedEvents};
+    use crate::input::Input;
+    use super::FrameLimiter;
+
+    #[test]
+    fn waiting_lasts_the_whole_frame_even_with_input_queued() {
+        let events = ScriptedEvents::new().frame(0.0, vec![mouse_move(1, 1), mouse_move(2, 2)]);
+        let input = Input::with_source(Box::new(events));
+
+        let mut limiter = FrameLimiter::new(50);
+        limiter.begin_frame();
+        limiter.wait(&input);
+        assert!(limiter.frame_start.elapsed() >= Duration::from_millis(20));
+
+        let start = Instant::now();
+        FrameLimiter::new(0).wait(&input);
+        assert!(start.elapsed() < Duration::from_millis(20));
+    }
+}
@@ -1,156 +1,591 @@
 use std::collections::HashMap;
 use std::io::Error;
-use std::time::Duration;
 use crossterm::event;
-use crossterm::event::{read, Event};
-
-pub enum InputEvent {
-    Down,
-    Up,
-}
+use crossterm::event::{Event, KeyCode, KeyModifiers};
+use serde::{Deserialize, Serialize};
+use crate::event_source::EventSource;
 
+#[derive(Clone, Copy)]
 pub enum MouseButton {
     Left,
     Right,
     Middle,
 }
 
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum KeyboardMode {
+    // Press, repeat and release events arrive through the kitty keyboard protocol
+    Enhanced,
+    // The terminal reports releases on its own, like the Windows console does
+    Native,
+    // Only presses arrive, so every press is immediately followed by a synthesized release
+    Synthesized,
+}
+
+impl KeyboardMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            KeyboardMode::Enhanced => "enhanced",
+            KeyboardMode::Native => "native",
+            KeyboardMode::Synthesized => "synthesized",
+        }
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Key(pub KeyCode);
+
+impl From<char> for Key {
+    fn from(c: char) -> Key {
+        Key(KeyCode::Char(c))
+    }
+}
+
+impl From<KeyCode> for Key {
+    fn from(code: KeyCode) -> Key {
+        Key(code)
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct ButtonState {
+    held: bool,
+    presses: u8,
+    releases: u8,
+    repeats: u8,
+    modifiers: KeyModifiers,
+}
+
+impl Default for ButtonState {
+    fn default() -> ButtonState {
+        ButtonState {
+            held: false,
+            presses: 0,
+            releases: 0,
+            repeats: 0,
+            modifiers: KeyModifiers::NONE,
+        }
+    }
+}
+
+impl ButtonState {
+    // Held, or pressed at some point during this frame
+    pub fn is_down(&self) -> bool {
+        self.held || self.presses > 0
+    }
+
+    pub fn is_held(&self) -> bool {
+        self.held
+    }
+
+    pub fn presses(&self) -> u8 {
+        self.presses
+    }
+
+    pub fn releases(&self) -> u8 {
+        self.releases
+    }
+
+    pub fn repeats(&self) -> u8 {
+        self.repeats
+    }
+
+    // The modifiers that were held the last time this was pressed
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.modifiers
+    }
+
+    fn press(&mut self, modifiers: KeyModifiers) {
+        self.modifiers = modifiers;
+        self.held = true;
+        self.presses = self.presses.saturating_add(1);
+    }
+
+    fn release(&mut self) {
+        self.held = false;
+        self.releases = self.releases.saturating_add(1);
+    }
+
+    fn repeat(&mut self) {
+        self.held = true;
+        self.repeats = self.repeats.saturating_add(1);
+    }
+
+    fn is_idle(&self) -> bool {
+        !self.held && self.presses == 0 && self.releases == 0 && self.repeats == 0
+    }
+
+    fn next_frame(&mut self) {
+        self.presses = 0;
+        self.releases = 0;
+        self.repeats = 0;
+    }
+}
+
+const DOUBLE_CLICK_TIME: f64 = 0.4;
+const DOUBLE_CLICK_DISTANCE: u16 = 1;
+
+#[derive(Clone, Copy)]
+pub struct Drag {
+    pub start: (u16, u16),
+    pub end: (u16, u16),
+    // Set in the frame the button was let go
+    pub finished: bool,
+}
+
+struct Click {
+    button: event::MouseButton,
+    position: (u16, u16),
+    time: f64,
+}
+
 pub struct Input {
-    keymap: HashMap<event::KeyCode, Option<InputEvent>>,
+    source: Box<dyn EventSource>,
+    dt: f64,
+    time: f64,
+    blocked: bool,
+
+    keyboard_mode: KeyboardMode,
+    keymap: HashMap<KeyCode, ButtonState>,
+    modifiers: KeyModifiers,
     resize: Option<(u16, u16)>,
+    focused: bool,
 
-    mouse_position: (u16, u16),
-    mousemap: HashMap<event::MouseButton, Option<InputEvent>>,
+    // Where the screen's drawing area starts in the terminal, mouse positions are relative to it
+    origin: (u16, u16),
+    // None until the first mouse event, so that one doesn't look like a jump from the corner
+    mouse_position: Option<(u16, u16)>,
+    mouse_delta: (i16, i16),
+    scroll_delta: (i16, i16),
+    mousemap: HashMap<event::MouseButton, ButtonState>,
+    drags: HashMap<event::MouseButton, Drag>,
+    last_click: Option<Click>,
+    double_clicks: Vec<event::MouseButton>,
 }
 
 impl Input {
-    pub fn new() -> Input {
+    pub fn with_source(source: Box<dyn EventSource>) -> Input {
         Input {
+            source,
+            dt: 0.0,
+            time: 0.0,
+            blocked: false,
+            keyboard_mode: KeyboardMode::Native,
             keymap: HashMap::new(),
+            modifiers: KeyModifiers::NONE,
             resize: None,
-            mouse_position: (0, 0),
+            focused: true,
+            origin: (0, 0),
+            mouse_position: None,
+            mouse_delta: (0, 0),
+            scroll_delta: (0, 0),
             mousemap: HashMap::new(),
+            drags: HashMap::new(),
+            last_click: None,
+            double_clicks: Vec::new(),
         }
     }
-    
-    pub fn keymap(&self) -> &HashMap<event::KeyCode, Option<InputEvent>> {
-        &self.keymap
+
+    pub fn delta_time(&self) -> f64 {
+        self.dt
     }
-    
-    pub fn mousemap(&self) -> &HashMap<event::MouseButton, Option<InputEvent>> {
-        &self.mousemap
+
+    // A scripted or replayed source ran out of frames
+    pub fn is_finished(&self) -> bool {
+        self.source.is_finished()
+    }
+
+    pub fn keyboard_mode(&self) -> KeyboardMode {
+        self.keyboard_mode
+    }
+
+    pub fn set_keyboard_mode(&mut self, mode: KeyboardMode) {
+        self.keyboard_mode = mode;
+    }
+
+    // While blocked every key and mouse query reports nothing, used for states under an overlay
+    pub fn set_blocked(&mut self, blocked: bool) {
+        self.blocked = blocked;
+    }
+
+    pub fn set_origin(&mut self, origin: (u16, u16)) {
+        self.origin = origin;
+    }
+
+    pub fn keymap(&self) -> &HashMap<KeyCode, ButtonState> {
+        &self.keymap
     }
 
     pub fn mouse_position(&self) -> (u16, u16) {
-        self.mouse_position
+        if self.blocked {
+            // Far away, so nothing underneath an overlay looks hovered
+            return (u16::MAX, u16::MAX);
+        }
+        self.mouse_position.unwrap_or((0, 0))
     }
-    
-    pub fn is_key_down(&self, key: char) -> bool {
-        let key = event::KeyCode::Char(key);
-        if let Some(event) = self.keymap.get(&key) {
-            if let Some(InputEvent::Down) = event {
-                return true;
-            }
+
+    // How far the mouse moved during this frame
+    pub fn mouse_delta(&self) -> (i16, i16) {
+        if self.blocked {
+            return (0, 0);
+        }
+        self.mouse_delta
+    }
+
+    // Wheel notches this frame as (x, y), positive is right and down
+    pub fn scroll_delta(&self) -> (i16, i16) {
+        if self.blocked {
+            return (0, 0);
         }
+        self.scroll_delta
+    }
+
+    // Active drag with this button, or one that ended this frame
+    pub fn drag(&self, button: MouseButton) -> Option<Drag> {
+        let button = Input::to_crossterm_button(button);
+        self.drags
+            .get(&button)
+            .filter(|drag| !self.blocked && drag.start != drag.end)
+            .copied()
+    }
 
-        false
+    pub fn is_double_click(&self, button: MouseButton) -> bool {
+        let button = Input::to_crossterm_button(button);
+        !self.blocked && self.double_clicks.contains(&button)
     }
     
-    pub fn is_key_up(&self, key: char) -> bool {
-        let key = event::KeyCode::Char(key);
-        if let Some(event) = self.keymap.get(&key) {
-            if let Some(InputEvent::Up) = event {
-                return true;
-            }
+    fn key_state(&self, key: impl Into<Key>) -> Option<&ButtonState> {
+        if self.blocked {
+            return None;
         }
+        self.keymap.get(&key.into().0)
+    }
 
-        false
+    fn mouse_state(&self, button: MouseButton) -> Option<&ButtonState> {
+        if self.blocked {
+            return None;
+        }
+        self.mousemap.get(&Input::to_crossterm_button(button))
     }
 
-    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
-        let button = match button {
-            MouseButton::Left => event::MouseButton::Left,
-            MouseButton::Right => event::MouseButton::Right,
-            MouseButton::Middle => event::MouseButton::Middle,
-        };
-        
-        if let Some(event) = self.mousemap.get(&button) {
-            if let Some(InputEvent::Down) = event {
-                return true;
-            }
+    // Modifiers reported with the most recent key event
+    pub fn modifiers(&self) -> KeyModifiers {
+        self.modifiers
+    }
+
+    pub fn is_key_pressed(&self, key: impl Into<Key>) -> bool {
+        self.key_state(key).is_some_and(|state| state.presses() > 0)
+    }
+
+    // Down since before this frame, a key pressed this frame only counts from the next one
+    pub fn is_key_held(&self, key: impl Into<Key>) -> bool {
+        self.key_state(key).is_some_and(|state| state.is_held() && state.presses() == 0)
+    }
+
+    pub fn is_key_released(&self, key: impl Into<Key>) -> bool {
+        self.key_state(key).is_some_and(|state| state.releases() > 0)
+    }
+
+    pub fn is_key_repeated(&self, key: impl Into<Key>) -> bool {
+        self.key_state(key).is_some_and(|state| state.repeats() > 0)
+    }
+
+    // Pressed or auto-repeated this frame, which is what menu navigation wants
+    pub fn is_key_typed(&self, key: impl Into<Key>) -> bool {
+        self.key_state(key).is_some_and(|state| state.presses() > 0 || state.repeats() > 0)
+    }
+
+    // Direction of an arrow key typed this frame, as (dx, dy)
+    pub fn arrow_direction(&self) -> Option<(i16, i16)> {
+        if self.is_key_typed(KeyCode::Left) {
+            Some((-1, 0))
+        } else if self.is_key_typed(KeyCode::Right) {
+            Some((1, 0))
+        } else if self.is_key_typed(KeyCode::Up) {
+            Some((0, -1))
+        } else if self.is_key_typed(KeyCode::Down) {
+            Some((0, 1))
+        } else {
+            None
         }
-        
-        false
+    }
+
+    // Enter or space, the keys that activate whatever has keyboard focus
+    pub fn is_confirm_pressed(&self) -> bool {
+        self.is_key_pressed(KeyCode::Enter) || self.is_key_pressed(' ')
+    }
+
+    // Pressed this frame while exactly these modifiers were held, e.g. Ctrl+C
+    pub fn is_shortcut_pressed(&self, modifiers: KeyModifiers, key: impl Into<Key>) -> bool {
+        self.key_state(key).is_some_and(|state| state.presses() > 0 && state.modifiers() == modifiers)
+    }
+
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_state(button).is_some_and(|state| state.presses() > 0)
+    }
+
+    pub fn is_mouse_down(&self, button: MouseButton) -> bool {
+        self.mouse_state(button).is_some_and(ButtonState::is_down)
     }
 
     pub fn is_mouse_up(&self, button: MouseButton) -> bool {
-        let button = match button {
+        self.mouse_state(button).is_some_and(|state| state.releases() > 0)
+    }
+
+    fn to_crossterm_button(button: MouseButton) -> event::MouseButton {
+        match button {
             MouseButton::Left => event::MouseButton::Left,
             MouseButton::Right => event::MouseButton::Right,
             MouseButton::Middle => event::MouseButton::Middle,
-        };
-        
-        if let Some(event) = self.mousemap.get(&button) {
-            if let Some(InputEvent::Up) = event {
-                return true;
-            }
         }
-        
-        false
     }
 
     pub fn resized(&self) -> Option<(u16, u16)> {
         self.resize
     }
 
+    // False while another window has focus, only reported by terminals that support it
+    pub fn has_focus(&self) -> bool {
+        self.focused
+    }
+
     pub fn update(&mut self) -> Result<(), Error> {
-        for (_, event) in self.mousemap.iter_mut() {
-            if let Some(InputEvent::Up) = event {
-                *event = None;
-            }
+        for state in self.mousemap.values_mut() {
+            state.next_frame();
         }
-        
-        self.keymap.retain(|_, event| {
-            if let Some(InputEvent::Up) = event {
-                false
-            } else {
-                true
-            }
-        });
-        
+
+        for state in self.keymap.values_mut() {
+            state.next_frame();
+        }
+        self.keymap.retain(|_, state| !state.is_idle());
+
+        self.drags.retain(|_, drag| !drag.finished);
+        self.double_clicks.clear();
+        self.mouse_delta = (0, 0);
+        self.scroll_delta = (0, 0);
         self.resize = None;
-        
-        if event::poll(Duration::from_millis(0))? {
-            let raw = read();
 
-            if raw.is_err() {
-                return Err(raw.err().unwrap());
-            }
+        self.dt = self.source.begin_frame()?;
+        self.time += self.dt;
 
-            let event = raw?;
+        // Handle everything that queued up since the last frame, in order
+        while let Some(event) = self.source.poll_event()? {
+            self.handle_event(event);
+        }
 
-            if let Event::Key(event) = event {
-                if event.kind == event::KeyEventKind::Press {
-                    self.keymap.insert(event.code, Some(InputEvent::Down));
-                }
-                else if event.kind == event::KeyEventKind::Release {
-                    self.keymap.insert(event.code, Some(InputEvent::Up));
+        Ok(())
+    }
+
+    fn handle_event(&mut self, event: Event) {
+        match event {
+            Event::Key(event) => {
+                self.modifiers = event.modifiers;
+                let state = self.keymap.entry(event.code).or_default();
+                match event.kind {
+                    event::KeyEventKind::Press => {
+                        state.press(event.modifiers);
+                        if self.keyboard_mode == KeyboardMode::Synthesized {
+                            state.release();
+                        }
+                    }
+                    event::KeyEventKind::Repeat => {
+                        state.repeat();
+                    }
+                    event::KeyEventKind::Release => {
+                        // A real release means the terminal reports them after all
+                        if self.keyboard_mode == KeyboardMode::Synthesized {
+                            self.keyboard_mode = KeyboardMode::Native;
+                            if !state.is_held() {
+                                return;
+                            }
+                        }
+                        state.release();
+                    }
                 }
             }
-            else if let Event::Mouse(event) = event {
-                self.mouse_position = (event.column, event.row);
-                if let event::MouseEventKind::Down(button) = event.kind {
-                    self.mousemap.insert(button, Some(InputEvent::Down));
+            Event::Mouse(event) => {
+                // Left of or above the drawing area wraps around to far away, like a blocked mouse,
+                // while the deltas below still come out right once cast back to signed
+                let position = (event.column.wrapping_sub(self.origin.0), event.row.wrapping_sub(self.origin.1));
+                if let Some(previous) = self.mouse_position {
+                    self.mouse_delta.0 += position.0 as i16 - previous.0 as i16;
+                    self.mouse_delta.1 += position.1 as i16 - previous.1 as i16;
                 }
-                else if let event::MouseEventKind::Up(button) = event.kind {
-                    self.mousemap.insert(button, Some(InputEvent::Up));
+                self.mouse_position = Some(position);
+
+                match event.kind {
+                    event::MouseEventKind::Down(button) => {
+                        self.mousemap.entry(button).or_default().press(event.modifiers);
+                        self.drags.insert(button, Drag { start: position, end: position, finished: false });
+                        self.register_click(button, position);
+                    }
+                    event::MouseEventKind::Up(button) => {
+                        self.mousemap.entry(button).or_default().release();
+                        if let Some(drag) = self.drags.get_mut(&button) {
+                            drag.end = position;
+                            drag.finished = true;
+                        }
+                    }
+                    event::MouseEventKind::Drag(button) => {
+                        if let Some(drag) = self.drags.get_mut(&button) {
+                            drag.end = position;
+                        }
+                    }
+                    event::MouseEventKind::ScrollUp => self.scroll_delta.1 -= 1,
+                    event::MouseEventKind::ScrollDown => self.scroll_delta.1 += 1,
+                    event::MouseEventKind::ScrollLeft => self.scroll_delta.0 -= 1,
+                    event::MouseEventKind::ScrollRight => self.scroll_delta.0 += 1,
+                    event::MouseEventKind::Moved => {}
                 }
             }
-            else if let Event::Resize(width, height) = event {
+            Event::Resize(width, height) => {
                 self.resize = Some((width, height));
             }
+            Event::FocusGained => self.focused = true,
+            Event::FocusLost => {
+                self.focused = false;
+
+                // Releases happen in the other window, so don't leave keys stuck down
+                for state in self.keymap.values_mut().chain(self.mousemap.values_mut()) {
+                    if state.is_held() {
+                        state.release();
+                    }
+                }
+            }
+            _ => {}
         }
+    }
 
-        Ok(())
+    fn register_click(&mut self, button: event::MouseButton, position: (u16, u16)) {
+        let is_double_click = self.last_click.as_ref().is_some_and(|click| {
+            click.button == button
+                && self.time - click.time <= DOUBLE_CLICK_TIME
+                && click.position.0.abs_diff(position.0) <= DOUBLE_CLICK_DISTANCE
+                && click.position.1.abs_diff(position.1) <= DOUBLE_CLICK_DISTANCE
+        });
+
+        if is_double_click {
+            self.double_clicks.push(button);
+            // A third click starts a new double click instead of completing another one
+            self.last_click = None;
+        } else {
+            self.last_click = Some(Click { button, position, time: self.time });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{Event, KeyCode};
+    use crossterm::event::{KeyEvent, KeyEventKind, KeyModifiers};
+    use crate::event_source::{key_press, key_release, mouse_move, ScriptedEvents};
+    use super::{Input, KeyboardMode};
+
+    fn input_with(frames: Vec<Vec<Event>>) -> Input {
+        let events = frames.into_iter().fold(ScriptedEvents::new(), |events, frame| events.frame(0.1, frame));
+        let mut input = Input::with_source(Box::new(events));
+        input.set_keyboard_mode(KeyboardMode::Native);
+        input
+    }
+
+    #[test]
+    fn every_press_and_release_in_a_frame_is_counted() {
+        let enter = KeyCode::Enter;
+        let mut input = input_with(vec![
+            vec![key_press(enter), key_release(enter), key_press(enter), key_release(enter)],
+            vec![],
+        ]);
+
+        input.update().unwrap();
+        let state = input.keymap()[&enter];
+        assert_eq!((state.presses(), state.releases()), (2, 2));
+        assert!(input.is_key_pressed(enter));
+        assert!(input.is_key_released(enter));
+
+        input.update().unwrap();
+        assert!(!input.is_key_pressed(enter));
+        assert!(!input.is_key_released(enter));
+    }
+
+    #[test]
+    fn a_key_is_held_from_the_frame_after_it_was_pressed() {
+        let right = KeyCode::Right;
+        let repeat = Event::Key(KeyEvent::new_with_kind(right, KeyModifiers::NONE, KeyEventKind::Repeat));
+        let mut input = input_with(vec![vec![key_press(right)], vec![], vec![repeat], vec![key_release(right)]]);
+
+        input.update().unwrap();
+        assert!(input.is_key_pressed(right));
+        assert!(!input.is_key_held(right));
+
+        input.update().unwrap();
+        assert!(input.is_key_held(right));
+        assert!(!input.is_key_repeated(right));
+
+        input.update().unwrap();
+        assert!(input.is_key_held(right));
+        assert!(input.is_key_repeated(right));
+
+        input.update().unwrap();
+        assert!(!input.is_key_held(right));
+        assert!(input.is_key_released(right));
+    }
+
+    #[test]
+    fn the_first_mouse_event_does_not_move_the_mouse() {
+        let mut input = input_with(vec![vec![mouse_move(50, 20)], vec![mouse_move(53, 18)]]);
+
+        input.update().unwrap();
+        assert_eq!(input.mouse_position(), (50, 20));
+        assert_eq!(input.mouse_delta(), (0, 0));
+
+        input.update().unwrap();
+        assert_eq!(input.mouse_delta(), (3, -2));
+    }
+
+    #[test]
+    fn without_release_events_a_press_is_released_in_the_same_frame() {
+        let space = KeyCode::Char(' ');
+        let mut input = input_with(vec![vec![key_press(space)], vec![]]);
+        input.set_keyboard_mode(KeyboardMode::Synthesized);
+
+        input.update().unwrap();
+        assert!(input.is_key_pressed(space));
+        assert!(input.is_key_released(space));
+
+        input.update().unwrap();
+        assert!(!input.is_key_held(space));
+        assert!(!input.is_key_released(space));
+    }
+
+    #[test]
+    fn the_first_real_release_switches_to_native() {
+        let (a, b) = (KeyCode::Char('a'), KeyCode::Char('b'));
+        let mut input = input_with(vec![
+            vec![key_press(a)],
+            vec![key_release(a)],
+            vec![key_press(b)],
+            vec![],
+            vec![key_release(b)],
+        ]);
+        input.set_keyboard_mode(KeyboardMode::Synthesized);
+
+        input.update().unwrap();
+        assert!(input.is_key_released(a));
+
+        // Already let go when it was pressed, so the real release isn't counted twice
+        input.update().unwrap();
+        assert!(input.keyboard_mode() == KeyboardMode::Native);
+        assert!(!input.is_key_released(a));
+
+        input.update().unwrap();
+        assert!(input.is_key_pressed(b));
+        assert!(!input.is_key_released(b));
+
+        input.update().unwrap();
+        assert!(input.is_key_held(b));
+
+        input.update().unwrap();
+        assert!(input.is_key_released(b));
+        assert!(!input.is_key_held(b));
     }
-}
\ No newline at end of file
+}
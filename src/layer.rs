@@ -0,0 +1,51 @@
+use crate::canvas::Canvas;
+
+// The canvases a frame is built from, listed from the bottom up
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum LayerId {
+    // Scenery and ascii art, and where drawing goes unless a state picks something else
+    Background,
+    Particles,
+    // Text boxes, menus and the calendar
+    Ui,
+    // Whatever a transition covers the screen with
+    Transition,
+    Debug,
+}
+
+impl LayerId {
+    fn default_z(self) -> i32 {
+        match self {
+            LayerId::Background => 0,
+            LayerId::Particles => 10,
+            LayerId::Ui => 20,
+            LayerId::Transition => 30,
+            LayerId::Debug => 40,
+        }
+    }
+}
+
+pub struct Layer {
+    pub id: LayerId,
+    pub canvas: Canvas,
+    // Higher layers are drawn on top, ties go to the one created first
+    pub z: i32,
+    // Where the canvas' top left corner ends up on the screen
+    pub offset: (i32, i32),
+    // From 0 for invisible to 1 for fully covering what's below
+    pub opacity: f64,
+    pub visible: bool,
+}
+
+impl Layer {
+    pub fn new(id: LayerId, width: i32, height: i32) -> Layer {
+        Layer {
+            id,
+            canvas: Canvas::new(width, height),
+            z: id.default_z(),
+            offset: (0, 0),
+            opacity: 1.0,
+            visible: true,
+        }
+    }
+}
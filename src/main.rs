@@ -1,64 +1,235 @@
 extern crate crossterm;
 mod ascii;
+mod backend;
 mod screen;
+mod canvas;
+mod layer;
+mod rect;
 mod snowflakes;
+mod particles;
 mod drawing;
 mod cannon_game;
 mod state_machine;
 mod input;
+mod event_source;
 mod transition;
+mod easing;
+mod animation;
 mod states;
-
+mod widgets;
+mod terminal_guard;
+mod context;
+mod config;
+mod recording;
+mod days;
+mod calendar;
+mod progress;
+mod theme;
+mod timestep;
+mod frame_limiter;
+#[cfg(test)]
+mod testing;
+
+use crate::backend::{Backend, MemoryBackend, TerminalBackend};
+use crate::config::{Config, USAGE};
+use crate::context::Context;
+use crate::event_source::{CrosstermEvents, EventSource, ScriptedEvents};
+use crate::frame_limiter::{FrameLimiter, DEFAULT_FPS};
+use crate::input::Input;
+use crate::layer::LayerId;
+use crate::progress::Progress;
+use crate::recording::{RecordingEvents, RecordingHeader};
 use crate::screen::Screen;
+use crossterm::event::KeyModifiers;
 use crossterm::terminal;
+use chrono::Local;
 use std::io::{stdout, Error};
-use std::time::Instant;
 
-use drawing::draw_debug_info;
+use drawing::{draw_debug_info, draw_too_small};
 use crate::states::transition_state::TransitionState;
+use crate::transition::TransitionConfig;
 
-fn delta_time(previous_time: &mut Instant) -> f64 {
-    let new_time = Instant::now();
-    let dt = new_time.duration_since(*previous_time).as_nanos() as f64 / 1_000_000_000.0;
-    *previous_time = new_time;
-    dt
-}
+// Headless runs don't have a terminal to ask for its size
+const HEADLESS_SIZE: (u16, u16) = (120, 50);
 
 fn main() -> Result<(), Error> {
-    let mut input = input::Input::new();
+    // Bad arguments are reported before raw mode, while the terminal still behaves
+    let mut config = match Config::from_args(std::env::args()) {
+        Ok(config) => config,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            std::process::exit(2);
+        }
+    };
+
+    if config.help {
+        println!("{}", USAGE);
+        return Ok(());
+    }
+
+    terminal_guard::install_panic_hook();
+
+    let replay = match &config.replay {
+        Some(path) => Some(recording::load_replay(path)?),
+        None => None,
+    };
+
+    // A replay runs with the settings it was recorded with, older recordings without a scale use this one
+    if let Some((header, _)) = &replay {
+        config.unlock_all = header.unlock_all;
+        config.day = header.day;
+        config.transition = header.transition;
+        config.scale = header.scale.unwrap_or(config.scale);
+    }
 
-    let mut screen = Screen::new(stdout(), terminal::size()?);
+    // Replays draw at the recorded size, which would wrap around in a smaller terminal
+    if let (Some((header, _)), None) = (&replay, config.headless) {
+        let (width, height) = terminal::size()?;
+        if width < header.size.0 || height < header.size.1 {
+            eprintln!(
+                "The recording was made in a {}x{} terminal, make this one at least that big to replay it (it is {}x{})",
+                header.size.0, header.size.1, width, height
+            );
+            std::process::exit(1);
+        }
+    }
+
+    let size = match (&replay, config.headless) {
+        (Some((header, _)), _) => header.size,
+        (None, Some(_)) => HEADLESS_SIZE,
+        (None, None) => terminal::size()?,
+    };
+
+    // Keep a handle on the memory backend to print the last frame after a headless run
+    let memory = MemoryBackend::new();
+    let backend: Box<dyn Backend> = match config.headless {
+        Some(_) => Box::new(memory.clone()),
+        None => Box::new(TerminalBackend::new(stdout())),
+    };
+
+    let mut screen = Screen::new(backend, size);
+    screen.set_theme(config.theme);
+    screen.set_scale_policy(config.scale);
     screen.init()?;
 
-    let initial_state = TransitionState::new(Box::new(states::main_state::MainState::new()), Some(transition::TransitionDirection::Out));
+    // Replays and headless runs shouldn't touch the real save file unless asked to
+    let progress = match &config.save {
+        Some(path) => Progress::load(path),
+        None if replay.is_none() && config.headless.is_none() => {
+            Progress::default_path().map_or_else(Progress::in_memory, |path| Progress::load(&path))
+        }
+        None => Progress::in_memory(),
+    };
+
+    let mut today = config.now.unwrap_or_else(|| Local::now().date_naive());
+
+    let (seed, mut input) = match replay {
+        Some((header, events)) => {
+            if let (None, Some(date)) = (config.now, header.today) {
+                today = date;
+            }
+
+            let mut input = Input::with_source(Box::new(events));
+            input.set_keyboard_mode(header.keyboard_mode);
+            (header.seed, input)
+        }
+        None => {
+            let seed = config.seed.unwrap_or_else(rand::random);
+            let mut source: Box<dyn EventSource> = match config.headless {
+                Some(frames) => {
+                    // Uncapped has no frame length to simulate, so fall back to the default
+                    let fps = if config.fps == 0 { DEFAULT_FPS } else { config.fps };
+                    let dt = 1.0 / fps as f64;
+                    Box::new(ScriptedEvents::new().idle(frames, dt))
+                }
+                None => Box::new(CrosstermEvents::new()),
+            };
+
+            if let Some(path) = &config.record {
+                let mut header = RecordingHeader::new(seed, size, screen.keyboard_mode(), today);
+                header.unlock_all = config.unlock_all;
+                header.day = config.day;
+                header.scale = Some(config.scale);
+                header.transition = config.transition;
+                source = Box::new(RecordingEvents::create(path, &header, source)?);
+            }
+
+            let mut input = Input::with_source(source);
+            input.set_keyboard_mode(screen.keyboard_mode());
+            (seed, input)
+        }
+    };
+    input.set_origin(screen.origin());
+
+    let mut ctx = Context::new(seed, today, config.unlock_all, progress);
+    ctx.transition = config.transition;
+
     let mut state_machine = state_machine::StateMachine::new();
-    state_machine.change(&mut screen, &mut input, Some(Box::new(initial_state)));
+    state_machine.push(&mut screen, &mut input, &mut ctx, Box::new(states::main_state::MainState::new()));
+    if let Some(entry) = config.day.and_then(days::find) {
+        ctx.progress.mark_opened(entry.day);
+        state_machine.push(&mut screen, &mut input, &mut ctx, (entry.create)());
+    }
+    state_machine.push(&mut screen, &mut input, &mut ctx, Box::new(TransitionState::reveal(TransitionConfig::default())));
 
-    let mut dt;
-    let mut previous_time = Instant::now();
+    let mut limiter = FrameLimiter::new(config.fps);
+    let mut frames = 0;
 
     loop {
-        if input.is_key_up('q') {
+        limiter.begin_frame();
+
+        if input.is_finished() || state_machine.is_empty() {
+            break;
+        }
+
+        if config.headless.is_some_and(|headless_frames| frames >= headless_frames) {
+            break;
+        }
+
+        if input.is_key_released('q') || input.is_shortcut_pressed(KeyModifiers::CONTROL, 'c') {
             break;
         }
 
         if let Some(size) = input.resized() {
             screen.resize(size);
+            input.set_origin(screen.origin());
         }
 
         screen.clear();
 
-        dt = delta_time(&mut previous_time);
+        // States still draw while the terminal is in the background, but time stands still
+        let dt = if input.has_focus() { input.delta_time() } else { 0.0 };
 
-        state_machine.update(&mut screen, &mut input, dt);
+        // Nothing runs until the terminal is big enough for every state that's showing
+        let min_size = state_machine.min_size();
+        if screen.width() < min_size.0 || screen.height() < min_size.1 {
+            draw_too_small(&mut screen, min_size);
+        } else {
+            state_machine.update(&mut screen, &mut input, &mut ctx, dt);
+        }
 
-        draw_debug_info(&mut screen, &mut input, dt);
+        if config.debug {
+            screen.set_layer(LayerId::Debug);
+            draw_debug_info(&mut screen, &mut input, dt);
+        }
 
         screen.render();
+        frames += 1;
+
+        // Headless frames have a fixed delta time, so there's nothing to wait for
+        if config.headless.is_none() {
+            limiter.wait(&input);
+        }
 
         input.update()?;
     }
 
+    // Gives every state still on the stack a chance to clean up, like saving
+    state_machine.clear(&mut screen, &mut input, &mut ctx);
     screen.cleanup()?;
+
+    if config.headless.is_some() {
+        print!("{}", memory.text());
+    }
     Ok(())
-}
\ No newline at end of file
+}
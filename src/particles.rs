@@ -0,0 +1,425 @@
+use crate::animation::Lerp;
+use crate::screen::Screen;
+use crate::theme::Theme;
+use crate::timestep::lerp;
+use crossterm::style::Color;
+use rand::Rng;
+
+// What happens to a particle that reaches the ground row
+#[derive(Clone, Copy, PartialEq)]
+pub enum GroundCollision {
+    Kill,
+    // Bounces back up with this share of its speed
+    Bounce(f64),
+}
+
+pub struct Particle {
+    x: f64,
+    y: f64,
+    // Position before the last step, drawing blends between the two
+    prev_x: f64,
+    prev_y: f64,
+    vx: f64,
+    vy: f64,
+    age: f64,
+    lifetime: Option<f64>,
+    // Keeps particles from swaying in lockstep
+    phase: f64,
+    sprite: char,
+}
+
+impl Particle {
+    // How far through its life the particle is, from 0 to 1
+    fn life(&self) -> f64 {
+        match self.lifetime {
+            Some(lifetime) if lifetime > 0.0 => (self.age / lifetime).min(1.0),
+            _ => 0.0,
+        }
+    }
+}
+
+// Spawns particles from a rectangle and moves them along until they die
+pub struct Emitter {
+    particles: Vec<Particle>,
+    position: (f64, f64),
+    area: (f64, f64),
+    rate: f64,
+    // Fractional particles left over from the last update
+    pending: f64,
+    time: f64,
+    max_particles: usize,
+    speed: (f64, f64),
+    angle: (f64, f64),
+    lifetime: Option<(f64, f64)>,
+    gravity: f64,
+    wind: f64,
+    // Air speed per column that particles with drag get carried along by
+    wind_field: Vec<f64>,
+    drag: f64,
+    sway: (f64, f64),
+    sprites: &'static [char],
+    colors: Vec<Color>,
+    bounds: (f64, f64),
+    ground: Option<GroundCollision>,
+}
+
+impl Emitter {
+    pub fn new(width: i32, height: i32) -> Emitter {
+        Emitter {
+            particles: Vec::new(),
+            position: (0.0, 0.0),
+            area: (0.0, 0.0),
+            rate: 0.0,
+            pending: 0.0,
+            time: 0.0,
+            max_particles: 500,
+            speed: (0.0, 0.0),
+            angle: (0.0, 0.0),
+            lifetime: None,
+            gravity: 0.0,
+            wind: 0.0,
+            wind_field: Vec::new(),
+            drag: 0.0,
+            sway: (0.0, 0.0),
+            sprites: &['*'],
+            colors: Vec::new(),
+            bounds: (width as f64, height as f64),
+            ground: None,
+        }
+    }
+
+    pub fn position(mut self, x: f64, y: f64) -> Emitter {
+        self.position = (x, y);
+        self
+    }
+
+    // Size of the rectangle new particles appear in, starting at the position
+    pub fn area(mut self, width: f64, height: f64) -> Emitter {
+        self.area = (width, height);
+        self
+    }
+
+    // Particles per second
+    pub fn rate(mut self, rate: f64) -> Emitter {
+        self.rate = rate;
+        self
+    }
+
+    // The oldest particles make room once there are more than this
+    pub fn max_particles(mut self, max_particles: usize) -> Emitter {
+        self.max_particles = max_particles;
+        self
+    }
+
+    // Cells per second
+    pub fn speed(mut self, min: f64, max: f64) -> Emitter {
+        self.speed = (min, max);
+        self
+    }
+
+    // Radians, where 0 is to the right and a quarter turn is straight down
+    pub fn angle(mut self, min: f64, max: f64) -> Emitter {
+        self.angle = (min, max);
+        self
+    }
+
+    // Seconds, particles without one live until they leave the screen
+    pub fn lifetime(mut self, min: f64, max: f64) -> Emitter {
+        self.lifetime = Some((min, max));
+        self
+    }
+
+    // Downwards acceleration in cells per second squared
+    pub fn gravity(mut self, gravity: f64) -> Emitter {
+        self.gravity = gravity;
+        self
+    }
+
+    // Sideways acceleration in cells per second squared
+    pub fn wind(mut self, wind: f64) -> Emitter {
+        self.wind = wind;
+        self
+    }
+
+    // How quickly particles pick up the speed of the wind field, per second
+    pub fn drag(mut self, drag: f64) -> Emitter {
+        self.drag = drag;
+        self
+    }
+
+    // Sideways drift back and forth, in cells per second and waves per second
+    pub fn sway(mut self, amplitude: f64, frequency: f64) -> Emitter {
+        self.sway = (amplitude, frequency);
+        self
+    }
+
+    pub fn sprites(mut self, sprites: &'static [char]) -> Emitter {
+        self.sprites = sprites;
+        self
+    }
+
+    // Blended between over each particle's life, the theme's text color without any
+    pub fn colors(mut self, colors: Vec<Color>) -> Emitter {
+        self.colors = colors;
+        self
+    }
+
+    // The bottom row of the screen is the ground
+    pub fn ground(mut self, collision: GroundCollision) -> Emitter {
+        self.ground = Some(collision);
+        self
+    }
+
+    pub fn set_position(&mut self, x: f64, y: f64) {
+        self.position = (x, y);
+    }
+
+    pub fn set_rate(&mut self, rate: f64) {
+        self.rate = rate;
+    }
+
+    pub fn set_wind_field(&mut self, wind_field: &[f64]) {
+        self.wind_field.clear();
+        self.wind_field.extend_from_slice(wind_field);
+    }
+
+    pub fn resize(&mut self, width: i32, height: i32) {
+        self.bounds = (width as f64, height as f64);
+    }
+
+    // Spawns particles right away, on top of the steady rate
+    pub fn emit(&mut self, rng: &mut impl Rng, count: usize) {
+        for _ in 0..count {
+            let x = self.position.0 + rng.random::<f64>() * self.area.0;
+            let y = self.position.1 + rng.random::<f64>() * self.area.1;
+            self.spawn(rng, x, y);
+        }
+    }
+
+    // Spawns particles anywhere above the ground, so an effect can start out already running
+    pub fn scatter(&mut self, rng: &mut impl Rng, count: usize) {
+        for _ in 0..count {
+            let x = (self.bounds.0 * rng.random::<f64>()).floor();
+            let y = ((self.bounds.1 - 1.0).max(0.0) * rng.random::<f64>()).floor();
+            self.spawn(rng, x, y);
+        }
+    }
+
+    pub fn update(&mut self, rng: &mut impl Rng, dt: f64) {
+        self.time += dt;
+
+        self.pending += self.rate * dt;
+        let count = self.pending.floor();
+        self.pending -= count;
+        self.emit(rng, count as usize);
+
+        let (width, height) = self.bounds;
+        let ground_level = height - 1.0;
+        let (sway_amplitude, sway_frequency) = self.sway;
+
+        for particle in self.particles.iter_mut() {
+            particle.prev_x = particle.x;
+            particle.prev_y = particle.y;
+
+            let air = self.wind_field.get(particle.x as usize).copied().unwrap_or(0.0);
+            particle.vx += (air - particle.vx) * (self.drag * dt).min(1.0);
+            particle.vx += self.wind * dt;
+            particle.vy += self.gravity * dt;
+            particle.x += particle.vx * dt;
+            particle.y += particle.vy * dt;
+            particle.x += (self.time * sway_frequency + particle.phase).sin() * sway_amplitude * dt;
+            particle.age += dt;
+
+            match self.ground {
+                Some(GroundCollision::Bounce(restitution)) if particle.y >= ground_level && particle.vy > 0.0 => {
+                    particle.y = ground_level - 0.5;
+                    particle.vy = -particle.vy * restitution;
+                }
+                _ => {}
+            }
+        }
+
+        let ground = self.ground;
+        self.particles.retain(|particle| {
+            let expired = particle.lifetime.is_some_and(|lifetime| particle.age >= lifetime);
+            let grounded = ground == Some(GroundCollision::Kill) && particle.y >= ground_level;
+            let outside = particle.x < 0.0 || particle.x >= width || particle.y >= height;
+            !expired && !grounded && !outside
+        });
+
+        if self.particles.len() > self.max_particles {
+            let excess = self.particles.len() - self.max_particles;
+            self.particles.drain(..excess);
+        }
+    }
+
+    // Removes the particles that `hit` says ran into something, given x and the y before and after the last step
+    pub fn collide(&mut self, mut hit: impl FnMut(f64, f64, f64) -> bool) {
+        self.particles.retain(|particle| !hit(particle.x, particle.prev_y, particle.y));
+    }
+
+    pub fn draw(&self, screen: &mut Screen, alpha: f64) {
+        for particle in &self.particles {
+            let x = lerp(particle.prev_x, particle.x, alpha).floor();
+            let y = lerp(particle.prev_y, particle.y, alpha).floor();
+
+            let color = self.color_at(particle.life()).unwrap_or(screen.theme().text);
+            screen.set_cell(x as i32, y as i32, particle.sprite, color);
+        }
+    }
+
+    fn spawn(&mut self, rng: &mut impl Rng, x: f64, y: f64) {
+        let speed = random_between(rng, self.speed);
+        let angle = random_between(rng, self.angle);
+        let lifetime = self.lifetime.map(|range| random_between(rng, range));
+        let sprite = self.sprites[rng.random_range(0..self.sprites.len())];
+
+        self.particles.push(Particle {
+            x,
+            y,
+            prev_x: x,
+            prev_y: y,
+            vx: speed * angle.cos(),
+            vy: speed * angle.sin(),
+            age: 0.0,
+            lifetime,
+            phase: rng.random::<f64>() * std::f64::consts::TAU,
+            sprite,
+        });
+    }
+
+    fn color_at(&self, life: f64) -> Option<Color> {
+        match self.colors.len() {
+            0 => None,
+            1 => Some(self.colors[0]),
+            len => {
+                let position = life * (len - 1) as f64;
+                let index = (position.floor() as usize).min(len - 2);
+                Some(Color::lerp(self.colors[index], self.colors[index + 1], position - index as f64))
+            }
+        }
+    }
+}
+
+// These colors followed by the theme's background, for particles that fade away. A terminal's own
+// background could be any color and blending into it would mean black, so then they don't fade.
+pub fn fading_colors(colors: &[Color], theme: &Theme) -> Vec<Color> {
+    let mut colors = colors.to_vec();
+    if theme.background != Color::Reset {
+        colors.push(theme.background);
+    }
+    colors
+}
+
+fn random_between(rng: &mut impl Rng, (min, max): (f64, f64)) -> f64 {
+    if max > min {
+        rng.random_range(min..max)
+    } else {
+        min
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::theme;
+    use super::{fading_colors, Emitter, GroundCollision};
+
+    fn rng() -> StdRng {
+        StdRng::seed_from_u64(7)
+    }
+
+    // Particles that stay where they're spawned, on a screen big enough to keep them
+    fn still_emitter() -> Emitter {
+        Emitter::new(100, 100).position(50.0, 50.0)
+    }
+
+    #[test]
+    fn fractions_of_a_particle_add_up_across_updates() {
+        let mut rng = rng();
+        let mut emitter = still_emitter().rate(2.0);
+
+        let counts: Vec<usize> = (0..4)
+            .map(|_| {
+                emitter.update(&mut rng, 0.25);
+                emitter.particles.len()
+            })
+            .collect();
+        assert_eq!(counts, vec![0, 1, 1, 2]);
+    }
+
+    #[test]
+    fn particles_die_at_the_end_of_their_lifetime() {
+        let mut rng = rng();
+        let mut emitter = still_emitter().lifetime(1.0, 1.0);
+        emitter.emit(&mut rng, 3);
+
+        emitter.update(&mut rng, 0.5);
+        assert_eq!(emitter.particles.len(), 3);
+        emitter.update(&mut rng, 0.5);
+        assert!(emitter.particles.is_empty());
+    }
+
+    // Falls from just above the ground row of a 10 high screen onto it in one step
+    fn falling(ground: Option<GroundCollision>) -> Emitter {
+        let mut rng = rng();
+        let mut emitter = Emitter::new(10, 10).position(5.0, 8.5).gravity(2.0);
+        if let Some(ground) = ground {
+            emitter = emitter.ground(ground);
+        }
+        emitter.emit(&mut rng, 1);
+        emitter.update(&mut rng, 0.5);
+        emitter
+    }
+
+    #[test]
+    fn the_ground_can_kill_particles() {
+        assert_eq!(falling(None).particles.len(), 1);
+        assert!(falling(Some(GroundCollision::Kill)).particles.is_empty());
+    }
+
+    #[test]
+    fn the_ground_can_bounce_particles_back_up() {
+        let emitter = falling(Some(GroundCollision::Bounce(0.5)));
+        let particle = &emitter.particles[0];
+        assert_eq!(particle.y, 8.5);
+        assert_eq!(particle.vy, -0.5);
+    }
+
+    #[test]
+    fn the_oldest_particles_make_room_for_new_ones() {
+        let mut rng = rng();
+        let mut emitter = still_emitter().max_particles(3);
+        for x in 0..5 {
+            emitter.set_position(x as f64, 50.0);
+            emitter.emit(&mut rng, 1);
+        }
+
+        emitter.update(&mut rng, 0.0);
+        let xs: Vec<f64> = emitter.particles.iter().map(|particle| particle.x).collect();
+        assert_eq!(xs, vec![2.0, 3.0, 4.0]);
+    }
+
+    #[test]
+    fn colors_blend_over_a_particles_life() {
+        let mut rng = rng();
+        let (from, to) = (Color::Rgb { r: 0, g: 0, b: 0 }, Color::Rgb { r: 200, g: 100, b: 0 });
+        let mut emitter = still_emitter().lifetime(2.0, 2.0).colors(vec![from, to]);
+        emitter.emit(&mut rng, 1);
+
+        assert_eq!(emitter.color_at(emitter.particles[0].life()), Some(from));
+        emitter.update(&mut rng, 1.0);
+        assert_eq!(emitter.color_at(emitter.particles[0].life()), Some(Color::Rgb { r: 100, g: 50, b: 0 }));
+    }
+
+    #[test]
+    fn only_a_real_background_is_faded_into() {
+        assert_eq!(fading_colors(&[Color::White], &theme::CLASSIC), vec![Color::White]);
+        assert_eq!(fading_colors(&[Color::White], &theme::MONO), vec![Color::White]);
+
+        let frost = theme::FROST;
+        assert_eq!(fading_colors(&[frost.text], &frost), vec![frost.text, frost.background]);
+    }
+}
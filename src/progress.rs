@@ -0,0 +1,206 @@
+use std::collections::BTreeMap;
+use std::fs::{self, File};
+use std::io::{Error, ErrorKind, Write};
+use std::path::{Path, PathBuf};
+use serde::{Deserialize, Serialize};
+
+// Bump when a change can't be read by older builds, new fields just need a default
+const PROGRESS_VERSION: u32 = 1;
+
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DayProgress {
+    pub opened: bool,
+    pub completed: bool,
+    // Higher is better
+    pub best_score: Option<u32>,
+    // Fewer is better
+    pub best_moves: Option<u32>,
+    // Fastest completion in seconds
+    pub best_time: Option<f64>,
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(default)]
+pub struct Progress {
+    pub version: u32,
+    pub days: BTreeMap<usize, DayProgress>,
+
+    // Where to save, None keeps everything in memory
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl Default for Progress {
+    fn default() -> Self {
+        Progress {
+            version: PROGRESS_VERSION,
+            days: BTreeMap::new(),
+            path: None,
+        }
+    }
+}
+
+impl Progress {
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::data_dir().map(|dir| dir.join("fgrust").join("progress.json"))
+    }
+
+    // Progress that is never written anywhere, for replays
+    pub fn in_memory() -> Progress {
+        Progress::default()
+    }
+
+    // A missing file starts fresh and a corrupt one is moved aside, so this never fails
+    pub fn load(path: &Path) -> Progress {
+        let mut progress = match fs::read_to_string(path) {
+            Ok(text) => match serde_json::from_str::<Progress>(&text) {
+                Ok(progress) => progress,
+                Err(_) => {
+                    let _ = fs::rename(path, path.with_extension("json.corrupt"));
+                    Progress::default()
+                }
+            },
+            Err(error) if error.kind() == ErrorKind::NotFound => Progress::default(),
+            // The file may still be fine, saving over it could lose real progress
+            Err(_) => return Progress::default(),
+        };
+
+        // Saving would throw away whatever a newer version added, so leave that file alone
+        if progress.version <= PROGRESS_VERSION {
+            progress.version = PROGRESS_VERSION;
+            progress.path = Some(path.to_path_buf());
+        }
+        progress
+    }
+
+    pub fn day(&self, day: usize) -> Option<&DayProgress> {
+        self.days.get(&day)
+    }
+
+    pub fn is_opened(&self, day: usize) -> bool {
+        self.day(day).is_some_and(|progress| progress.opened)
+    }
+
+    pub fn is_completed(&self, day: usize) -> bool {
+        self.day(day).is_some_and(|progress| progress.completed)
+    }
+
+    pub fn mark_opened(&mut self, day: usize) {
+        self.days.entry(day).or_default().opened = true;
+        self.save_quietly();
+    }
+
+    // Keeps the best of each result across attempts
+    pub fn complete(&mut self, day: usize, score: Option<u32>, moves: Option<u32>, time: f64) {
+        let progress = self.days.entry(day).or_default();
+        progress.opened = true;
+        progress.completed = true;
+        progress.best_score = progress.best_score.max(score);
+        progress.best_moves = match (progress.best_moves, moves) {
+            (Some(best), Some(moves)) => Some(best.min(moves)),
+            (best, moves) => best.or(moves),
+        };
+        progress.best_time = Some(progress.best_time.map_or(time, |best| best.min(time)));
+        self.save_quietly();
+    }
+
+    pub fn save(&self) -> Result<(), Error> {
+        let path = match &self.path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+
+        let json = serde_json::to_string_pretty(self)
+            .map_err(|error| Error::new(ErrorKind::InvalidData, error))?;
+
+        // Write next to the real file and swap it in, so a crash never leaves half a save
+        let tmp_path = path.with_extension("json.tmp");
+        let mut file = File::create(&tmp_path)?;
+        file.write_all(json.as_bytes())?;
+        file.sync_all()?;
+        fs::rename(&tmp_path, path)
+    }
+
+    // Progress is nice to have, a read-only data dir shouldn't stop the game
+    fn save_quietly(&self) {
+        let _ = self.save();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+    use std::path::PathBuf;
+    use super::Progress;
+
+    // A fresh directory per test, so tests running at once don't share files
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("fgrust-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn saved_progress_loads_back() {
+        let path = temp_dir("round-trip").join("progress.json");
+
+        let mut progress = Progress::load(&path);
+        progress.mark_opened(3);
+        progress.complete(5, Some(10), Some(20), 12.5);
+
+        let loaded = Progress::load(&path);
+        assert!(loaded.is_opened(3));
+        assert!(!loaded.is_completed(3));
+        let day = loaded.day(5).unwrap();
+        assert!(day.completed);
+        assert_eq!(day.best_score, Some(10));
+        assert_eq!(day.best_moves, Some(20));
+        assert_eq!(day.best_time, Some(12.5));
+    }
+
+    #[test]
+    fn saving_swaps_in_a_complete_file() {
+        let dir = temp_dir("atomic");
+        let path = dir.join("progress.json");
+        fs::write(&path, "{\"version\": 1}").unwrap();
+
+        let mut progress = Progress::load(&path);
+        progress.mark_opened(1);
+
+        assert!(!path.with_extension("json.tmp").exists());
+        assert!(Progress::load(&path).is_opened(1));
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+    }
+
+    #[test]
+    fn a_corrupt_file_is_moved_aside() {
+        let path = temp_dir("corrupt").join("progress.json");
+        fs::write(&path, "not json").unwrap();
+
+        let mut progress = Progress::load(&path);
+        assert!(!progress.is_opened(1));
+        assert_eq!(fs::read_to_string(path.with_extension("json.corrupt")).unwrap(), "not json");
+
+        progress.mark_opened(1);
+        assert!(Progress::load(&path).is_opened(1));
+    }
+
+    #[test]
+    fn a_file_that_cant_be_read_is_never_overwritten() {
+        // A directory where the file should be fails to read with something other than NotFound
+        let path = temp_dir("unreadable").join("progress.json");
+        fs::create_dir(&path).unwrap();
+
+        let mut progress = Progress::load(&path);
+        progress.mark_opened(1);
+
+        assert!(path.is_dir());
+        assert!(!path.with_extension("json.tmp").exists());
+    }
+}
@@ -0,0 +1,206 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Error, ErrorKind, Write};
+use std::path::Path;
+use chrono::NaiveDate;
+use crossterm::event::Event;
+use serde::{Deserialize, Serialize};
+use crate::event_source::{EventSource, ScriptedEvents};
+use crate::input::KeyboardMode;
+use crate::screen::ScalePolicy;
+use crate::transition::TransitionEffect;
+
+const RECORDING_VERSION: u32 = 1;
+
+// First line of a recording, everything needed to start the session the same way
+#[derive(Serialize, Deserialize)]
+pub struct RecordingHeader {
+    pub version: u32,
+    pub seed: u64,
+    pub size: (u16, u16),
+    pub keyboard_mode: KeyboardMode,
+    // Doors unlock by date, so a replay has to run on the same day
+    #[serde(default)]
+    pub today: Option<NaiveDate>,
+    // Or with every door open, whatever the date
+    #[serde(default)]
+    pub unlock_all: bool,
+    // Settings from the command line that change what happens, a replay uses these instead of its own
+    #[serde(default)]
+    pub day: Option<usize>,
+    #[serde(default)]
+    pub scale: Option<ScalePolicy>,
+    #[serde(default)]
+    pub transition: Option<TransitionEffect>,
+}
+
+impl RecordingHeader {
+    pub fn new(seed: u64, size: (u16, u16), keyboard_mode: KeyboardMode, today: NaiveDate) -> RecordingHeader {
+        RecordingHeader {
+            version: RECORDING_VERSION,
+            seed,
+            size,
+            keyboard_mode,
+            today: Some(today),
+            unlock_all: false,
+            day: None,
+            scale: None,
+            transition: None,
+        }
+    }
+}
+
+// One line per frame after the header
+#[derive(Serialize, Deserialize)]
+struct RecordedFrame {
+    dt: f64,
+    events: Vec<Event>,
+}
+
+// Passes events through from another source while writing every frame to a file
+pub struct RecordingEvents {
+    source: Box<dyn EventSource>,
+    writer: BufWriter<File>,
+    frame: Option<RecordedFrame>,
+}
+
+impl RecordingEvents {
+    pub fn create(path: &Path, header: &RecordingHeader, source: Box<dyn EventSource>) -> Result<RecordingEvents, Error> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, header)?;
+        writer.write_all(b"\n")?;
+
+        Ok(RecordingEvents {
+            source,
+            writer,
+            frame: None,
+        })
+    }
+
+    fn write_frame(&mut self) -> Result<(), Error> {
+        if let Some(frame) = self.frame.take() {
+            serde_json::to_writer(&mut self.writer, &frame)?;
+            self.writer.write_all(b"\n")?;
+        }
+        Ok(())
+    }
+}
+
+impl EventSource for RecordingEvents {
+    fn begin_frame(&mut self) -> Result<f64, Error> {
+        self.write_frame()?;
+
+        let dt = self.source.begin_frame()?;
+        // Asking past the end of the source isn't a frame, a replay would run it as one
+        if !self.source.is_finished() {
+            self.frame = Some(RecordedFrame { dt, events: vec![] });
+        }
+        Ok(dt)
+    }
+
+    fn poll_event(&mut self) -> Result<Option<Event>, Error> {
+        let event = self.source.poll_event()?;
+        if let (Some(event), Some(frame)) = (&event, &mut self.frame) {
+            frame.events.push(event.clone());
+        }
+        Ok(event)
+    }
+
+    fn is_finished(&self) -> bool {
+        self.source.is_finished()
+    }
+}
+
+impl Drop for RecordingEvents {
+    fn drop(&mut self) {
+        let _ = self.write_frame();
+        let _ = self.writer.flush();
+    }
+}
+
+pub fn load_replay(path: &Path) -> Result<(RecordingHeader, ScriptedEvents), Error> {
+    let mut lines = BufReader::new(File::open(path)?).lines();
+
+    let header: RecordingHeader = match lines.next() {
+        Some(line) => serde_json::from_str(&line?)?,
+        None => return Err(Error::new(ErrorKind::InvalidData, "recording is empty")),
+    };
+
+    if header.version != RECORDING_VERSION {
+        let message = format!("unsupported recording version {}", header.version);
+        return Err(Error::new(ErrorKind::InvalidData, message));
+    }
+
+    let mut events = ScriptedEvents::new();
+    for line in lines {
+        let line = line?;
+        if line.is_empty() {
+            continue;
+        }
+
+        let frame: RecordedFrame = serde_json::from_str(&line)?;
+        events.push_frame(frame.dt, frame.events);
+    }
+
+    Ok((header, events))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{Event, KeyCode, MouseButton};
+    use crate::event_source::{key_press, mouse_down, EventSource, ScriptedEvents};
+    use crate::input::KeyboardMode;
+    use crate::screen::ScalePolicy;
+    use crate::testing::date;
+    use crate::transition::TransitionEffect;
+    use super::{load_replay, RecordingEvents, RecordingHeader};
+
+    // Every frame a source gives, as (dt, events)
+    fn drain(source: &mut dyn EventSource) -> Vec<(f64, Vec<Event>)> {
+        let mut frames = Vec::new();
+        loop {
+            let dt = source.begin_frame().unwrap();
+            if source.is_finished() {
+                return frames;
+            }
+
+            let mut events = Vec::new();
+            while let Some(event) = source.poll_event().unwrap() {
+                events.push(event);
+            }
+            frames.push((dt, events));
+        }
+    }
+
+    #[test]
+    fn a_recorded_session_replays_the_same() {
+        let path = std::env::temp_dir().join(format!("fgrust-recording-{}.jsonl", std::process::id()));
+        let script = || ScriptedEvents::new()
+            .frame(0.016, vec![key_press(KeyCode::Right)])
+            .frame(0.020, vec![])
+            .frame(0.017, vec![mouse_down(MouseButton::Left, 4, 7), key_press(KeyCode::Enter)]);
+
+        let mut header = RecordingHeader::new(42, (100, 40), KeyboardMode::Enhanced, date(2024, 12, 3));
+        header.unlock_all = true;
+        header.day = Some(2);
+        header.scale = Some(ScalePolicy::Fill);
+        header.transition = Some(TransitionEffect::WipeUp);
+
+        let mut recording = RecordingEvents::create(&path, &header, Box::new(script())).unwrap();
+        let recorded = drain(&mut recording);
+        drop(recording);
+
+        let (loaded, mut replay) = load_replay(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!((loaded.seed, loaded.size), (42, (100, 40)));
+        assert!(loaded.keyboard_mode == KeyboardMode::Enhanced);
+        assert_eq!(loaded.today, Some(date(2024, 12, 3)));
+        assert!(loaded.unlock_all);
+        assert_eq!(loaded.day, Some(2));
+        assert_eq!(loaded.scale, Some(ScalePolicy::Fill));
+        assert!(loaded.transition == Some(TransitionEffect::WipeUp));
+
+        assert_eq!(recorded, drain(&mut script()));
+        assert_eq!(drain(&mut replay), recorded);
+    }
+}
@@ -1,159 +1,326 @@
-use std::io::{Stdout, Write};
-use crossterm::{cursor, queue, style, terminal};
-use crossterm::cursor::MoveTo;
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture};
-use crossterm::style::{SetForegroundColor, Stylize};
-use crossterm::terminal::{disable_raw_mode, enable_raw_mode, Clear, ClearType};
-
-#[derive(Clone)]
-pub struct Cell {
-    pub rune: char,
-    pub color: style::Color,
+use crossterm::style;
+use crossterm::style::Attributes;
+use serde::{Deserialize, Serialize};
+use crate::backend::Backend;
+use crate::canvas::{Canvas, Cell};
+use crate::input::KeyboardMode;
+use crate::layer::{Layer, LayerId};
+use crate::rect::Rect;
+use crate::theme::{self, Theme};
+
+// How the drawing area fits inside the terminal
+#[derive(Clone, Copy, PartialEq, Debug, Serialize, Deserialize)]
+pub enum ScalePolicy {
+    // Use every cell the terminal has
+    Fill,
+    // Never grow past this size, bigger terminals get an empty border around it
+    Center { width: u16, height: u16 },
 }
 
-impl Cell {
-    fn new(c: char, color: style::Color) -> Cell {
-        Cell {rune: c, color }
-    }
+// Past this the art and text boxes get lost in the middle of the screen
+pub const MAX_CANVAS_SIZE: (u16, u16) = (200, 70);
 
-    fn clear(&mut self) {
-        self.set(' ', style::Color::White);
+impl ScalePolicy {
+    pub fn find(name: &str) -> Option<ScalePolicy> {
+        match name {
+            "fill" => Some(ScalePolicy::Fill),
+            "center" => Some(ScalePolicy::Center { width: MAX_CANVAS_SIZE.0, height: MAX_CANVAS_SIZE.1 }),
+            _ => None,
+        }
     }
 
-    fn set(&mut self, c: char, color: style::Color) {
-        self.rune = c;
-        self.color = color;
+    // Size of the drawing area and where its top left corner goes in the terminal
+    fn fit(&self, (width, height): (u16, u16)) -> ((u16, u16), (u16, u16)) {
+        match *self {
+            ScalePolicy::Fill => ((width, height), (0, 0)),
+            ScalePolicy::Center { width: max_width, height: max_height } => {
+                let size = (width.min(max_width), height.min(max_height));
+                (size, ((width - size.0) / 2, (height - size.1) / 2))
+            }
+        }
     }
 }
 
 pub struct Screen {
-    stdout: Stdout,
+    backend: Box<dyn Backend>,
     width: u16,
     height: u16,
-    real_width: u16,
-    real_height: u16,
-    buffer: Vec<Cell>,
+    terminal_size: (u16, u16),
+    origin: (u16, u16),
+    scale_policy: ScalePolicy,
+    // Created the first time something draws into them, in no particular order
+    layers: Vec<Layer>,
+    // Where drawing goes until another layer is picked
+    current_layer: usize,
+    // Every visible layer flattened together, as it was last presented
+    frame: Canvas,
+    bytes_written: usize,
+    theme: Theme,
 }
 
 impl Screen {
-    pub fn new(stdout: Stdout, size: (u16, u16)) -> Screen {
-        let (width, height) = Screen::clamp_screen(size);
+    pub fn new(backend: Box<dyn Backend>, size: (u16, u16)) -> Screen {
         let mut screen = Screen {
-            stdout,
-            width,
-            height,
-            real_width: size.0,
-            real_height: size.1,
-            buffer: Vec::new(),
+            backend,
+            width: 0,
+            height: 0,
+            terminal_size: size,
+            origin: (0, 0),
+            scale_policy: ScalePolicy::Fill,
+            layers: Vec::new(),
+            current_layer: 0,
+            frame: Canvas::new(0, 0),
+            bytes_written: 0,
+            theme: theme::CLASSIC,
         };
 
         screen.resize(size);
+        screen.set_layer(LayerId::Background);
         screen
     }
 
-    pub fn width(&self) -> u16 {
-        self.width
+    pub fn width(&self) -> i32 {
+        self.width as i32
     }
 
-    pub fn height(&self) -> u16 {
-        self.height
+    pub fn height(&self) -> i32 {
+        self.height as i32
     }
-    
-    pub fn clone_buffer(&self) -> Vec<Cell> {
-        self.buffer.clone()
+
+    // Restricts drawing to a part of what's already allowed, until the matching pop
+    pub fn push_clip(&mut self, rect: Rect) {
+        self.canvas_mut().push_clip(rect);
     }
 
-    pub fn init(&mut self) -> Result<(), std::io::Error> {
-        enable_raw_mode()?;
+    pub fn pop_clip(&mut self) {
+        self.canvas_mut().pop_clip();
+    }
 
-        queue!(
-            self.stdout,
-            terminal::EnterAlternateScreen,
-            cursor::Hide,
-            EnableMouseCapture,
-            Clear(ClearType::All)
-        )?;
-        Ok(())
+    // Where the drawing area's top left corner is in the terminal
+    pub fn origin(&self) -> (u16, u16) {
+        self.origin
     }
 
-    pub fn cleanup(&mut self) -> Result<(), std::io::Error> {
-        disable_raw_mode()?;
+    pub fn terminal_size(&self) -> (u16, u16) {
+        self.terminal_size
+    }
+
+    pub fn set_scale_policy(&mut self, policy: ScalePolicy) {
+        self.scale_policy = policy;
+        self.resize(self.terminal_size);
+    }
+
+    pub fn bytes_written(&self) -> usize {
+        self.bytes_written
+    }
+
+    pub fn theme(&self) -> &Theme {
+        &self.theme
+    }
+
+    pub fn set_theme(&mut self, theme: Theme) {
+        self.theme = theme;
+    }
 
-        queue!(
-            self.stdout,
-            cursor::Show,
-            terminal::LeaveAlternateScreen,
-            DisableMouseCapture
-        )?;
+    pub fn keyboard_mode(&self) -> KeyboardMode {
+        self.backend.keyboard_mode()
+    }
 
-        self.stdout.flush()?;
-        Ok(())
+    pub fn init(&mut self) -> Result<(), std::io::Error> {
+        self.backend.init()
+    }
+
+    pub fn cleanup(&mut self) -> Result<(), std::io::Error> {
+        self.backend.cleanup()
     }
 
+    // Empties every layer and goes back to drawing on the background
     pub fn clear(&mut self) {
-        for i in 0..self.buffer.len() {
-            self.buffer[i].clear();
+        let transparent = Cell::transparent();
+        for layer in self.layers.iter_mut() {
+            layer.canvas.fill(&transparent);
         }
+        self.set_layer(LayerId::Background);
     }
 
     pub fn resize(&mut self, size: (u16, u16)) {
-        let (width, height) = Screen::clamp_screen(size);
+        let ((width, height), origin) = self.scale_policy.fit(size);
         self.width = width;
         self.height = height;
-        self.real_width = size.0;
-        self.real_height = size.1;
+        self.terminal_size = size;
+        self.origin = origin;
 
-        if self.buffer.len() == (width * height) as usize {
-            return;
-        }
+        // The terminal may have reflowed or cleared its contents, so repaint everything
+        self.backend.invalidate();
 
-        self.buffer.clear();
-        for _ in 0..(width * height) {
-            self.buffer.push(Cell::new(' ', style::Color::White));
+        for layer in self.layers.iter_mut() {
+            layer.canvas.resize(self.width as i32, self.height as i32);
         }
-        self.clear();
+        self.frame.resize(self.width as i32, self.height as i32);
     }
 
-    fn clamp_screen((width, height) : (u16, u16)) -> (u16, u16) {
-        let new_width = (width - 1).clamp(80, 200);
-        let new_height = height.clamp(40, 70);
-        (new_width, new_height)
+    // Sends everything drawn from now on to this layer
+    pub fn set_layer(&mut self, id: LayerId) {
+        self.layer_mut(id);
+        self.current_layer = self.layers.iter().position(|layer| layer.id == id).unwrap_or(0);
     }
 
-    pub fn set_cell(&mut self, x: u16, y: u16, c: char, color: style::Color) {
-        let index = self.xy_to_index(x, y);
+    pub fn current_layer(&self) -> LayerId {
+        self.layers[self.current_layer].id
+    }
 
-        if x >= self.width || y >= self.height {
-            return;
-        }
+    // For moving, fading or hiding a layer, which keeps its settings from frame to frame
+    pub fn layer_mut(&mut self, id: LayerId) -> &mut Layer {
+        let index = match self.layers.iter().position(|layer| layer.id == id) {
+            Some(index) => index,
+            None => {
+                self.layers.push(Layer::new(id, self.width as i32, self.height as i32));
+                self.layers.len() - 1
+            }
+        };
+        &mut self.layers[index]
+    }
 
-        if x >= self.real_width || y >= self.real_height {
-            return;
-        }
+    // Everything drawn so far this frame on the layers under this one, flattened into one canvas
+    pub fn snapshot(&mut self, below: LayerId) -> Canvas {
+        let z = self.layer_mut(below).z;
+        let mut snapshot = Canvas::new(self.width as i32, self.height as i32);
+        Screen::composite(&self.layers, &mut snapshot, &self.theme, z);
+        snapshot
+    }
 
-        self.buffer[index].set(c, color);
+    fn canvas(&self) -> &Canvas {
+        &self.layers[self.current_layer].canvas
     }
 
-    pub fn xy_to_index(&self, x: u16, y: u16) -> usize {
-        (y * self.width + x) as usize
+    fn canvas_mut(&mut self) -> &mut Canvas {
+        &mut self.layers[self.current_layer].canvas
     }
 
-    pub fn index_to_xy(&self, index: usize) -> (u16, u16) {
-        let x = index as u16 % self.width;
-        let y = index as u16 / self.width;
-        (x, y)
+    // Wide characters take up this cell and the next, combining marks go on the cell before
+    pub fn set_cell(&mut self, x: i32, y: i32, c: char, color: style::Color) {
+        self.canvas_mut().set_cell(x, y, c, color);
     }
 
-    pub fn render(&mut self) {
-        for (i, cell) in self.buffer.iter().enumerate() {
-            let (x, y) = self.index_to_xy(i);
-            queue!(
-                self.stdout,
-                MoveTo(x, y),
-                SetForegroundColor(cell.color),
-                style::PrintStyledContent(cell.rune.stylize())
-            ).unwrap();
+    // Lays a whole canvas over the current layer, like a frame saved earlier
+    pub fn draw_canvas(&mut self, canvas: &Canvas, x: i32, y: i32) {
+        self.canvas_mut().draw_canvas(canvas, x, y, 1.0);
+    }
+
+    // Only sees the current layer, not anything drawn on the others
+    pub fn cell(&self, x: i32, y: i32) -> Option<&Cell> {
+        self.canvas().cell(x, y)
+    }
+
+    pub fn set_background(&mut self, x: i32, y: i32, color: style::Color) {
+        self.canvas_mut().set_background(x, y, color);
+    }
+
+    pub fn set_attributes(&mut self, x: i32, y: i32, attributes: Attributes) {
+        self.canvas_mut().set_attributes(x, y, attributes);
+    }
+
+    // Flattens the visible layers below max_z onto a blank canvas, lowest first
+    fn composite(layers: &[Layer], target: &mut Canvas, theme: &Theme, max_z: i32) {
+        target.fill(&Cell::blank(theme));
+
+        let mut visible: Vec<&Layer> = layers.iter().filter(|layer| layer.visible && layer.z < max_z).collect();
+        visible.sort_by_key(|layer| layer.z);
+        for layer in visible {
+            target.draw_canvas(&layer.canvas, layer.offset.0, layer.offset.1, layer.opacity);
         }
-        self.stdout.flush().unwrap();
+
+        target.repair_wide_characters();
+    }
+
+    pub fn render(&mut self) {
+        Screen::composite(&self.layers, &mut self.frame, &self.theme, i32::MAX);
+        self.bytes_written = self.backend
+            .present(self.frame.cells(), self.width, self.height, self.origin)
+            .unwrap();
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::style::Color;
+    use crate::backend::MemoryBackend;
+    use crate::layer::LayerId;
+    use super::Screen;
+
+    fn screen() -> (Screen, MemoryBackend) {
+        let memory = MemoryBackend::new();
+        (Screen::new(Box::new(memory.clone()), (6, 3)), memory)
+    }
+
+    fn rune(memory: &MemoryBackend, x: u16, y: u16) -> char {
+        memory.cell(x, y).unwrap().rune
+    }
+
+    #[test]
+    fn higher_layers_cover_lower_ones_whatever_order_they_were_drawn_in() {
+        let (mut screen, memory) = screen();
+        screen.set_layer(LayerId::Ui);
+        screen.set_cell(0, 0, 'u', Color::White);
+        screen.set_layer(LayerId::Background);
+        screen.set_cell(0, 0, 'b', Color::White);
+
+        screen.render();
+        assert_eq!(rune(&memory, 0, 0), 'u');
+
+        screen.layer_mut(LayerId::Background).z = 100;
+        screen.render();
+        assert_eq!(rune(&memory, 0, 0), 'b');
+    }
+
+    #[test]
+    fn empty_cells_show_the_layer_below() {
+        let (mut screen, memory) = screen();
+        screen.set_cell(1, 0, 'b', Color::White);
+        screen.set_layer(LayerId::Ui);
+        screen.set_cell(2, 0, 'u', Color::White);
+
+        screen.render();
+        assert_eq!((rune(&memory, 1, 0), rune(&memory, 2, 0), rune(&memory, 3, 0)), ('b', 'u', ' '));
+    }
+
+    #[test]
+    fn layers_are_moved_by_their_offset() {
+        let (mut screen, memory) = screen();
+        screen.set_layer(LayerId::Ui);
+        screen.set_cell(0, 0, 'u', Color::White);
+        screen.layer_mut(LayerId::Ui).offset = (2, 1);
+
+        screen.render();
+        assert_eq!(rune(&memory, 0, 0), ' ');
+        assert_eq!(rune(&memory, 2, 1), 'u');
+    }
+
+    #[test]
+    fn partly_transparent_layers_blend_into_the_ones_below() {
+        let (mut screen, memory) = screen();
+        screen.set_background(0, 0, Color::Rgb { r: 0, g: 0, b: 0 });
+        screen.set_layer(LayerId::Ui);
+        screen.set_background(0, 0, Color::Rgb { r: 200, g: 100, b: 0 });
+        screen.layer_mut(LayerId::Ui).opacity = 0.5;
+
+        screen.render();
+        assert_eq!(memory.cell(0, 0).unwrap().background, Some(Color::Rgb { r: 100, g: 50, b: 0 }));
+
+        screen.layer_mut(LayerId::Ui).opacity = 0.0;
+        screen.render();
+        assert_eq!(memory.cell(0, 0).unwrap().background, Some(Color::Rgb { r: 0, g: 0, b: 0 }));
+    }
+
+    #[test]
+    fn a_snapshot_only_has_the_layers_below() {
+        let (mut screen, _memory) = screen();
+        screen.set_cell(0, 0, 'b', Color::White);
+        screen.set_layer(LayerId::Transition);
+        screen.set_cell(1, 0, 't', Color::White);
+
+        let snapshot = screen.snapshot(LayerId::Transition);
+        assert_eq!(snapshot.cell(0, 0).unwrap().rune, 'b');
+        assert_eq!(snapshot.cell(1, 0).unwrap().rune, ' ');
+    }
+}
@@ -1,76 +1,331 @@
+use crate::canvas::Cell;
+use crate::drawing::{char_width, text_width};
+use crate::layer::LayerId;
+use crate::particles::Emitter;
 use crate::screen::Screen;
-use crossterm::style;
+use rand::Rng;
+use std::f64::consts::FRAC_PI_2;
 
+const SNOW_FLAKES: usize = 100;
 const MAX_SNOW_FLAKES: usize = 500;
-const SNOW_FLAKE_SPAWN_PROBABILITY: f64 = 0.7;
+const MOUSE_SNOW_FLAKE_RATE: f64 = 300.0;
+const MOUSE_SPAWN_SPREAD: f64 = 6.0;
 const SNOW_FLAKE_SPRITES: [char; 3] = ['*', '·', '•'];
 
-pub struct Snowflake {
-    x: f64,
-    y: f64,
-    speed: f64,
-    sprite: char,
+// How much a single landed flake adds to a pile, in cells
+const FLAKE_DEPTH: f64 = 0.25;
+const MAX_GROUND_DEPTH: f64 = 3.0;
+const MAX_SURFACE_DEPTH: f64 = 1.5;
+// Cells per second, so piles only last as long as it keeps snowing
+const MELT_RATE: f64 = 0.001;
+// Piles steeper than this between neighbouring columns slide down
+const MAX_SLOPE: f64 = 1.0;
+// Partial cells of snow, from an eighth up to seven eighths
+const PILE_SPRITES: [char; 7] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇'];
+
+const GUST_STRENGTH: f64 = 1.5;
+const MOUSE_PUSH: f64 = 4.0;
+const MOUSE_PUSH_RADIUS: f64 = 10.0;
+const MAX_PUSH: f64 = 15.0;
+// How quickly a push from the mouse dies down, per second
+const PUSH_DECAY: f64 = 1.5;
+
+// Snow collecting on one surface, one column at a time
+struct Pile {
+    x: i32,
+    // The row snow rests on in each column, none where there's nothing to land on
+    tops: Vec<Option<i32>>,
+    depths: Vec<f64>,
+    max_depth: f64,
 }
 
-pub fn create(width: u16, height: u16) -> Vec<Snowflake> {
-    let mut snow_flakes: Vec<Snowflake> = Vec::new();
-    for _ in 0..100 {
-        snow_flakes.push(Snowflake {
-            x: (width as f64 * rand::random::<f64>()).floor(),
-            y: ((height - 1) as f64 * rand::random::<f64>()).floor(),
-            speed: (rand::random::<f64>() * 1.0) + 0.5,
-            sprite: SNOW_FLAKE_SPRITES
-                [(rand::random::<u16>() % SNOW_FLAKE_SPRITES.len() as u16) as usize],
-        });
+impl Pile {
+    // Top of the snow in a screen column, or none if the pile doesn't cover it
+    fn surface(&self, column: i32) -> Option<f64> {
+        let i = usize::try_from(column - self.x).ok()?;
+        let top = (*self.tops.get(i)?)?;
+        Some(top as f64 - self.depths[i])
+    }
+
+    fn add(&mut self, column: i32, depth: f64) {
+        let i = (column - self.x) as usize;
+        self.depths[i] = (self.depths[i] + depth).min(self.max_depth);
+    }
+
+    fn settle(&mut self, dt: f64) {
+        for depth in self.depths.iter_mut() {
+            *depth = (*depth - MELT_RATE * dt).max(0.0);
+        }
+
+        for i in 1..self.depths.len() {
+            if self.tops[i].is_none() || self.tops[i - 1].is_none() {
+                continue;
+            }
+
+            let slope = self.depths[i] - self.depths[i - 1];
+            if slope.abs() > MAX_SLOPE {
+                let slide = (slope.abs() - MAX_SLOPE) / 2.0 * slope.signum();
+                self.depths[i] -= slide;
+                self.depths[i - 1] += slide;
+            }
+        }
     }
 
-    snow_flakes
+    fn draw(&self, screen: &mut Screen) {
+        for (i, (top, depth)) in self.tops.iter().zip(&self.depths).enumerate() {
+            let Some(top) = top else { continue };
+            let x = self.x + i as i32;
+            let full = depth.floor() as i32;
+
+            for row in 1..=full {
+                draw_snow(screen, x, top - row, '█');
+            }
+
+            let eighths = ((depth - depth.floor()) * 8.0) as usize;
+            if eighths > 0 {
+                draw_snow(screen, x, top - full - 1, PILE_SPRITES[eighths - 1]);
+            }
+        }
+    }
+}
+
+// Falling snow that the mouse can blow around and that builds up on the ground and on ascii art
+pub struct Snowfall {
+    flakes: Emitter,
+    mouse_flakes: Emitter,
+    piles: Vec<Pile>,
+    // Air speed per column, the gusts plus whatever the mouse has pushed
+    wind: Vec<f64>,
+    push: Vec<f64>,
+    time: f64,
 }
 
-pub fn update(snow_flakes: &mut Vec<Snowflake>, width: u16, height: u16, phase: f64, dt: f64) {
-    prune_snow_flakes(snow_flakes);
+impl Snowfall {
+    pub fn new(rng: &mut impl Rng, screen: &Screen) -> Snowfall {
+        let (width, height) = (screen.width(), screen.height());
+
+        // Flakes fall about a cell per second, so this keeps the count steady as they land
+        let rate = SNOW_FLAKES as f64 / height.max(1) as f64;
 
-    for snow_flake in snow_flakes.iter_mut() {
-        snow_flake.y += snow_flake.speed * dt;
-        snow_flake.x += (phase + snow_flake.x / 8.0).sin() * 1.0 * dt;
+        let mut flakes = falling_snow(screen)
+            .area(width as f64, 0.0)
+            .rate(rate)
+            .speed(0.5, 1.5);
+        flakes.scatter(rng, SNOW_FLAKES);
 
-        let ground_level = height - 1;
+        let mouse_flakes = falling_snow(screen)
+            .area(MOUSE_SPAWN_SPREAD, MOUSE_SPAWN_SPREAD)
+            .speed(2.8, 3.3);
 
-        if snow_flake.y as u16 >= ground_level {
-            snow_flake.y = 0.0;
-            snow_flake.x = (width - 1) as f64 * rand::random::<f64>();
+        let ground = Pile {
+            x: 0,
+            tops: vec![Some(height - 1); width as usize],
+            depths: vec![0.0; width as usize],
+            max_depth: MAX_GROUND_DEPTH,
+        };
+
+        Snowfall {
+            flakes,
+            mouse_flakes,
+            piles: vec![ground],
+            wind: vec![0.0; width as usize],
+            push: vec![0.0; width as usize],
+            time: 0.0,
+        }
+    }
+
+    // Lets snow settle on top of ascii art drawn at the same place with `draw_ascii`
+    pub fn settle_on(&mut self, ascii: &str, x: i32, y: i32) {
+        let width = ascii.lines().map(|line| text_width(line) as usize).max().unwrap_or(0);
+        let mut tops = vec![None; width];
+
+        for (row, line) in ascii.lines().enumerate() {
+            let mut column = 0;
+            for c in line.chars() {
+                let c_width = char_width(c) as usize;
+                if c != ' ' {
+                    for top in &mut tops[column..column + c_width] {
+                        top.get_or_insert(y + row as i32);
+                    }
+                }
+                column += c_width;
+            }
         }
 
-        snow_flake.x = snow_flake.x.clamp(0.0, (width - 1) as f64);
+        self.piles.push(Pile {
+            x,
+            depths: vec![0.0; width],
+            tops,
+            max_depth: MAX_SURFACE_DEPTH,
+        });
     }
-}
 
-fn prune_snow_flakes(snow_flakes: &mut Vec<Snowflake>) {
-    while snow_flakes.len() > MAX_SNOW_FLAKES {
-        snow_flakes.remove(0);
+    // Called once a frame, moving the mouse blows the snow along
+    pub fn push(&mut self, mouse_position: (u16, u16), mouse_delta: (i16, i16), mouse_down: bool) {
+        self.mouse_flakes.set_position(mouse_position.0 as f64, mouse_position.1 as f64 - 1.0);
+        self.mouse_flakes.set_rate(if mouse_down { MOUSE_SNOW_FLAKE_RATE } else { 0.0 });
+
+        if mouse_delta.0 == 0 {
+            return;
+        }
+
+        for (column, push) in self.push.iter_mut().enumerate() {
+            let distance = (column as f64 - mouse_position.0 as f64).abs();
+            if distance < MOUSE_PUSH_RADIUS {
+                let falloff = 1.0 - distance / MOUSE_PUSH_RADIUS;
+                *push = (*push + mouse_delta.0 as f64 * MOUSE_PUSH * falloff).clamp(-MAX_PUSH, MAX_PUSH);
+            }
+        }
+    }
+
+    pub fn update(&mut self, rng: &mut impl Rng, dt: f64) {
+        self.time += dt;
+
+        for (column, (wind, push)) in self.wind.iter_mut().zip(self.push.iter_mut()).enumerate() {
+            *push -= *push * (PUSH_DECAY * dt).min(1.0);
+            let gust = (self.time * 0.2 + column as f64 * 0.03).sin() * (self.time * 0.07).sin();
+            *wind = gust * GUST_STRENGTH + *push;
+        }
+
+        let piles = &mut self.piles;
+        for flakes in [&mut self.flakes, &mut self.mouse_flakes] {
+            flakes.set_wind_field(&self.wind);
+            flakes.update(rng, dt);
+            flakes.collide(|x, prev_y, y| land(piles, x, prev_y, y));
+        }
+
+        for pile in piles.iter_mut() {
+            pile.settle(dt);
+        }
+    }
+
+    // Piles go on the current layer with whatever they rest on, falling flakes above it on their own
+    pub fn draw(&self, screen: &mut Screen, alpha: f64) {
+        for pile in &self.piles {
+            pile.draw(screen);
+        }
+
+        let layer = screen.current_layer();
+        screen.set_layer(LayerId::Particles);
+        self.flakes.draw(screen, alpha);
+        self.mouse_flakes.draw(screen, alpha);
+        screen.set_layer(layer);
     }
 }
 
-pub fn spawn_mouse_snow_flakes(snow_flakes: &mut Vec<Snowflake>, mouse_position: (u16, u16)) {
-    if rand::random::<f64>() < SNOW_FLAKE_SPAWN_PROBABILITY {
-        const SPAWN_SPREAD: f64 = 6.0;
-        for i in 0..500 {
-            snow_flakes.push(Snowflake {
-                x: mouse_position.0 as f64 + (rand::random::<f64>() * SPAWN_SPREAD),
-                y: mouse_position.1 as f64 + (rand::random::<f64>() * SPAWN_SPREAD) - 1.0,
-                speed: (rand::random::<f64>() * 0.5) + 2.8,
-                sprite: SNOW_FLAKE_SPRITES
-                    [(rand::random::<u16>() % SNOW_FLAKE_SPRITES.len() as u16) as usize],
-            });
+// Adds a flake to the first pile it fell onto during the last step
+fn land(piles: &mut [Pile], x: f64, prev_y: f64, y: f64) -> bool {
+    let column = x.floor() as i32;
+
+    let landed_on = piles.iter_mut().find(|pile| {
+        pile.surface(column).is_some_and(|surface| prev_y < surface && y >= surface)
+    });
+
+    if let Some(pile) = landed_on {
+        pile.add(column, FLAKE_DEPTH);
+        return true;
+    }
+
+    // Anything that slipped past the ground still ends up on it
+    match piles.first_mut() {
+        Some(ground) if ground.surface(column).is_some_and(|surface| y >= surface) => {
+            ground.add(column, FLAKE_DEPTH);
+            true
         }
+        _ => false,
+    }
+}
+
+// Piles stay behind anything already drawn, like the bottom of art standing on the ground
+fn draw_snow(screen: &mut Screen, x: i32, y: i32, c: char) {
+    if screen.cell(x, y).is_some_and(Cell::is_blank) {
+        screen.set_cell(x, y, c, screen.theme().snow);
     }
 }
 
-pub fn draw(screen: &mut Screen, snow_flakes: &Vec<Snowflake>) {
-    for snow_flake in snow_flakes {
-        let x = snow_flake.x as u16;
-        let y = snow_flake.y as u16;
+fn falling_snow(screen: &Screen) -> Emitter {
+    Emitter::new(screen.width(), screen.height())
+        .angle(FRAC_PI_2, FRAC_PI_2)
+        .sway(1.0, 1.0)
+        .drag(1.5)
+        .sprites(&SNOW_FLAKE_SPRITES)
+        .colors(vec![screen.theme().snow])
+        .max_particles(MAX_SNOW_FLAKES)
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::backend::MemoryBackend;
+    use crate::screen::Screen;
+    use super::{land, Pile, Snowfall, FLAKE_DEPTH, MAX_GROUND_DEPTH, MAX_SLOPE, MELT_RATE};
+
+    // Ground along the bottom row of a 20x10 screen
+    fn snowfall() -> Snowfall {
+        let screen = Screen::new(Box::new(MemoryBackend::new()), (20, 10));
+        Snowfall::new(&mut StdRng::seed_from_u64(1), &screen)
+    }
+
+    fn ground(depths: Vec<f64>) -> Pile {
+        Pile {
+            x: 0,
+            tops: vec![Some(9); depths.len()],
+            depths,
+            max_depth: MAX_GROUND_DEPTH,
+        }
+    }
+
+    #[test]
+    fn flakes_pile_up_on_the_ground() {
+        let mut snowfall = snowfall();
+
+        assert!(!land(&mut snowfall.piles, 3.5, 7.0, 8.5));
+        assert!(land(&mut snowfall.piles, 3.5, 8.5, 9.2));
+        assert!(land(&mut snowfall.piles, 3.5, 8.0, 8.9));
+        assert_eq!(snowfall.piles[0].depths[3], FLAKE_DEPTH * 2.0);
+        assert_eq!(snowfall.piles[0].surface(3), Some(9.0 - FLAKE_DEPTH * 2.0));
+    }
+
+    #[test]
+    fn flakes_pile_up_on_ascii_art() {
+        let mut snowfall = snowfall();
+        snowfall.settle_on(" #\n##", 4, 5);
+
+        // The top of each column is its first non-space character
+        assert_eq!(snowfall.piles[1].tops, vec![Some(6), Some(5)]);
+
+        assert!(land(&mut snowfall.piles, 5.2, 4.5, 5.1));
+        assert!(land(&mut snowfall.piles, 4.2, 5.5, 6.1));
+        assert_eq!(snowfall.piles[1].depths, vec![FLAKE_DEPTH, FLAKE_DEPTH]);
+        assert!(snowfall.piles[0].depths.iter().all(|depth| *depth == 0.0));
+    }
+
+    #[test]
+    fn piles_steeper_than_the_max_slope_slide_down() {
+        let mut pile = ground(vec![0.0, 2.5, 2.5]);
+        pile.settle(0.0);
+
+        assert_eq!(pile.depths.iter().sum::<f64>(), 5.0);
+        assert!((pile.depths[1] - pile.depths[0]).abs() <= MAX_SLOPE);
+    }
+
+    #[test]
+    fn piles_stop_growing_at_their_max_depth() {
+        let mut pile = ground(vec![0.0]);
+        for _ in 0..100 {
+            pile.add(0, FLAKE_DEPTH);
+        }
+        assert_eq!(pile.depths[0], MAX_GROUND_DEPTH);
+    }
+
+    #[test]
+    fn piles_melt_away() {
+        let mut pile = ground(vec![1.0, 0.0]);
+        pile.settle(100.0);
+        assert!((pile.depths[0] - (1.0 - MELT_RATE * 100.0)).abs() < 1e-9);
 
-        screen.set_cell(x, y, snow_flake.sprite, style::Color::White);
+        pile.settle(1.0 / MELT_RATE);
+        assert_eq!(pile.depths, vec![0.0, 0.0]);
     }
 }
@@ -1,40 +1,238 @@
+use crate::context::Context;
 use crate::input::Input;
+use crate::layer::LayerId;
 use crate::screen::Screen;
+use crate::transition::TransitionConfig;
+
+pub enum StateChange {
+    Push(Box<dyn State>),
+    Pop,
+    Replace(Box<dyn State>),
+    // Applied in order, e.g. closing a menu and the day behind it
+    Batch(Vec<StateChange>),
+}
 
 pub trait State {
-    fn enter(&mut self, screen: &mut Screen, input: &mut Input);
-    fn update(&mut self, screen: &mut Screen, input: &mut Input, dt: f64) -> Option<Box<dyn State>>;
-    fn exit(&mut self, screen: &mut Screen, input: &mut Input);
+    fn enter(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context);
+    fn update(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, dt: f64) -> Option<StateChange>;
+    fn exit(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context);
+
+    // Another state was pushed on top of this one
+    fn pause(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {}
+
+    // This state is on top of the stack again
+    fn resume(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {}
+
+    // The states below keep drawing underneath this one
+    fn is_transparent(&self) -> bool {
+        false
+    }
+
+    // The states below keep running while this one is on top, without getting any input
+    fn updates_below(&self) -> bool {
+        false
+    }
+
+    // How this state wants to be transitioned to when it's opened
+    fn entry_transition(&self) -> TransitionConfig {
+        TransitionConfig::default()
+    }
+
+    // Smallest screen this state can be drawn on, as (width, height)
+    fn min_size(&self) -> (i32, i32) {
+        DEFAULT_MIN_SIZE
+    }
 }
 
+// What every state was laid out for before they could ask for something else
+pub const DEFAULT_MIN_SIZE: (i32, i32) = (80, 40);
+
 pub struct StateMachine {
-    current_state: Option<Box<dyn State>>,
+    stack: Vec<Box<dyn State>>,
 }
 
 impl StateMachine {
     pub fn new() -> StateMachine {
         StateMachine {
-            current_state: None,
+            stack: Vec::new(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stack.is_empty()
+    }
+
+    pub fn push(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, mut state: Box<dyn State>) {
+        if let Some(top) = self.stack.last_mut() {
+            top.pause(screen, input, ctx);
         }
+
+        state.enter(screen, input, ctx);
+        self.stack.push(state);
     }
-    
-    pub fn change(&mut self, screen: &mut Screen, input: &mut Input, new_state: Option<Box<dyn State>>) {
-        if let Some(ref mut state) = self.current_state {
-            state.exit(screen, input);
+
+    pub fn pop(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context) {
+        if let Some(mut state) = self.stack.pop() {
+            state.exit(screen, input, ctx);
         }
 
-        self.current_state = new_state;
+        if let Some(top) = self.stack.last_mut() {
+            top.resume(screen, input, ctx);
+        }
+    }
+
+    pub fn replace(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, mut state: Box<dyn State>) {
+        if let Some(mut old_state) = self.stack.pop() {
+            old_state.exit(screen, input, ctx);
+        }
+
+        state.enter(screen, input, ctx);
+        self.stack.push(state);
+    }
 
-        if let Some(ref mut state) = self.current_state {
-            state.enter(screen, input);
+    // Exits every state, top first
+    pub fn clear(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context) {
+        while let Some(mut state) = self.stack.pop() {
+            state.exit(screen, input, ctx);
         }
     }
 
-    pub fn update(&mut self, screen: &mut Screen, input: &mut Input, dt: f64) {
-        if let Some(ref mut state) = self.current_state {
-            if let Some(new_state) = state.update(screen, input, dt) {
-                self.change(screen, input, Some(new_state));
+    pub fn apply(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, change: StateChange) {
+        match change {
+            StateChange::Push(state) => self.push(screen, input, ctx, state),
+            StateChange::Pop => self.pop(screen, input, ctx),
+            StateChange::Replace(state) => self.replace(screen, input, ctx, state),
+            StateChange::Batch(changes) => {
+                for change in changes {
+                    self.apply(screen, input, ctx, change);
+                }
             }
         }
     }
-}
\ No newline at end of file
+
+    pub fn update(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, dt: f64) {
+        if self.stack.is_empty() {
+            return;
+        }
+
+        let top = self.stack.len() - 1;
+        let bottom = self.lowest_visible();
+
+        // Only the top state gets input and may change the stack
+        input.set_blocked(true);
+        for i in bottom..top {
+            let running = self.stack[i + 1..].iter().all(|state| state.updates_below());
+            let state_dt = if running { dt } else { 0.0 };
+            screen.set_layer(LayerId::Background);
+            let _ = self.stack[i].update(screen, input, ctx, state_dt);
+        }
+        input.set_blocked(false);
+
+        // Every state starts out drawing on the background, whatever the one below picked
+        screen.set_layer(LayerId::Background);
+
+        if let Some(change) = self.stack[top].update(screen, input, ctx, dt) {
+            self.apply(screen, input, ctx, change);
+        }
+    }
+
+    // The screen has to fit every state that's drawing, so the largest of their minimums
+    pub fn min_size(&self) -> (i32, i32) {
+        if self.stack.is_empty() {
+            return (0, 0);
+        }
+
+        self.stack[self.lowest_visible()..]
+            .iter()
+            .map(|state| state.min_size())
+            .fold((0, 0), |(width, height), size| (width.max(size.0), height.max(size.1)))
+    }
+
+    // The lowest state that can be seen through the transparent ones above it
+    fn lowest_visible(&self) -> usize {
+        let mut bottom = self.stack.len() - 1;
+        while bottom > 0 && self.stack[bottom].is_transparent() {
+            bottom -= 1;
+        }
+        bottom
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use crate::context::Context;
+    use crate::input::Input;
+    use crate::screen::Screen;
+    use crate::testing::{date, Harness};
+    use super::{State, StateChange};
+
+    // Writes down every hook it gets, and pops itself when asked to
+    struct Logged {
+        name: &'static str,
+        log: Rc<RefCell<Vec<String>>>,
+        pop: bool,
+    }
+
+    impl Logged {
+        fn new(name: &'static str, log: &Rc<RefCell<Vec<String>>>) -> Box<Logged> {
+            Box::new(Logged { name, log: log.clone(), pop: false })
+        }
+
+        fn note(&self, hook: &str) {
+            self.log.borrow_mut().push(format!("{} {}", hook, self.name));
+        }
+    }
+
+    impl State for Logged {
+        fn enter(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+            self.note("enter");
+        }
+
+        fn update(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context, _dt: f64) -> Option<StateChange> {
+            self.pop.then_some(StateChange::Pop)
+        }
+
+        fn exit(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+            self.note("exit");
+        }
+
+        fn pause(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+            self.note("pause");
+        }
+
+        fn resume(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+            self.note("resume");
+        }
+    }
+
+    #[test]
+    fn hooks_run_as_states_come_and_go() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut harness = Harness::new(date(2024, 12, 1));
+        harness.push(Logged::new("calendar", &log));
+
+        let mut menu = Logged::new("menu", &log);
+        menu.pop = true;
+        harness.push(menu);
+        harness.idle(1);
+
+        harness.push(Logged::new("day", &log));
+        let harness = &mut harness;
+        harness.state_machine.clear(&mut harness.screen, &mut harness.input, &mut harness.ctx);
+
+        assert_eq!(*log.borrow(), [
+            "enter calendar",
+            "pause calendar",
+            "enter menu",
+            "exit menu",
+            "resume calendar",
+            "pause calendar",
+            "enter day",
+            "exit day",
+            "exit calendar",
+        ]);
+        assert!(harness.state_machine.is_empty());
+    }
+}
@@ -1,25 +1,47 @@
-use crate::input::{Input, MouseButton};
+use crate::animation::Animation;
+use crate::particles::{fading_colors, Emitter};
+use crate::timestep::FixedTimestep;
+use std::f64::consts::FRAC_PI_2;
+use crate::context::Context;
+use std::time::Duration;
+use crate::easing::Easing;
+use crate::transition::{TransitionConfig, TransitionEffect};
+use crossterm::event::KeyCode;
+use crate::input::Input;
 use crate::screen::Screen;
-use crate::state_machine::State;
-use rand::{thread_rng, Rng};
-use crate::drawing::{draw_ascii, draw_question};
-use crate::states::main_state::MainState;
+use crate::layer::LayerId;
+use crate::state_machine::{State, StateChange};
+use rand::Rng;
+use crate::drawing::{draw_ascii, question_widgets};
+use crate::states::pause_state::PauseState;
 use crate::states::transition_state::TransitionState;
+use crate::days::{DayEntry, DayKind};
+use crate::widgets::label::Label;
+use crate::widgets::layout::VBox;
+use crate::widgets::ui::Ui;
 
-struct Particle {
-    x: f64,
-    y: f64,
-    sprite: char,
-}
+pub const DAY: DayEntry = DayEntry {
+    day: 1,
+    title: "Livets store spørsmål",
+    description: "Svar på ett enkelt spørsmål",
+    kind: DayKind::Puzzle,
+    create: || Box::new(Day1State::new()),
+};
+
+const Z_SPRITES: [char; 2] = ['Z', 'z'];
 
 pub struct Day1State {
     question: String,
     correct_answer: String,
     wrong_answers: [&'static str; 2],
     correct_answer_position: usize,
+    ui: Ui,
 
     phase: f64,
-    particles: Vec<Particle>,
+    sleeping_zs: Emitter,
+    timestep: FixedTimestep,
+    // Goes from 0 to 1 as the question slides up into place, once the entry transition is mostly done
+    slide_in: Animation<f64>,
 }
 
 impl Day1State {
@@ -27,81 +49,124 @@ impl Day1State {
         let question = "What is the answer to life, the universe, and everything?".to_string();
         let correct_answer = "42".to_string();
         let wrong_answers = ["24", "69"];
-        let number_of_answers = wrong_answers.len() + 1;
-        let correct_answer_position = thread_rng().gen_range(0..number_of_answers);
         Day1State {
             question,
             correct_answer,
             wrong_answers,
-            correct_answer_position,
+            correct_answer_position: 0,
+            ui: Ui::new(Label::new("")),
 
             phase: 0.0,
-            particles: vec![],
+            sleeping_zs: Emitter::new(0, 0),
+            timestep: FixedTimestep::default(),
+            slide_in: Animation::new(0.0)
+                .delay(0.8)
+                .to(1.0, 0.8, Easing::CubicOut),
         }
     }
 }
 
+impl Day1State {
+    fn question_widgets(&self, answered_correctly: Option<bool>) -> VBox {
+        let mut answers = self.wrong_answers.to_vec();
+        answers.insert(self.correct_answer_position, &self.correct_answer);
+        question_widgets(&self.question, &answers, answered_correctly)
+    }
+}
+
 impl State for Day1State {
-    fn enter(&mut self, screen: &mut Screen, input: &mut Input) {
-        self.particles = create_particles();
+    fn enter(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context) {
+        let number_of_answers = self.wrong_answers.len() + 1;
+        self.correct_answer_position = ctx.rng.random_range(0..number_of_answers);
+        self.sleeping_zs = create_sleeping_zs(screen);
+        self.ui.set_root(self.question_widgets(None));
     }
 
-    fn update(&mut self, screen: &mut Screen, input: &mut Input, dt: f64) -> Option<Box<dyn State>> {
+    fn update(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, dt: f64) -> Option<StateChange> {
 
         self.phase += dt;
+        self.slide_in.update(dt);
+
+        if input.resized().is_some() {
+            self.sleeping_zs.resize(screen.width(), screen.height());
+        }
 
-        draw_ascii(screen, TREE_FIREPLACE, screen.width() - 43, (screen.height() as i16 - 40).clamp(0, screen.height() as i16) as u16);
+        draw_ascii(screen, TREE_FIREPLACE, screen.width() - 43, screen.height() - 40);
         draw_ascii(screen, PRESENT, 12, screen.height() - 26);
 
         {
             let cat_x = screen.width() - 50;
             let cat_y = screen.height() - 12;
 
+            // Rising from the cat's head
+            self.sleeping_zs.set_position(cat_x as f64 + 4.0, cat_y as f64 + 1.0);
+            for _ in 0..self.timestep.advance(dt) {
+                self.sleeping_zs.update(&mut ctx.rng, self.timestep.step());
+            }
+
             draw_ascii(screen, LAZY_CAT, cat_x, cat_y);
-            draw_particles(screen, &mut self.particles, cat_x, cat_y, self.phase, dt);
+            screen.set_layer(LayerId::Particles);
+            self.sleeping_zs.draw(screen, self.timestep.alpha());
+        }
+
+        if input.is_key_pressed(KeyCode::Esc) || input.is_key_pressed('p') {
+            return Some(StateChange::Push(Box::new(PauseState::new())));
+        }
+
+        let number_of_answers = self.wrong_answers.len() + 1;
+        let mut chosen = None;
+
+        for answer in 0..number_of_answers {
+            let key = char::from_digit(answer as u32 + 1, 10).unwrap();
+            if input.is_key_pressed(key) {
+                self.ui.focus(Some(answer));
+                chosen = Some(answer);
+            }
+        }
+
+        // Starts low enough that the answer below the buttons stays on screen
+        let slide_distance = (screen.height() as f64 / 2.0 - 7.0).max(0.0);
+        let y_offset = ((1.0 - self.slide_in.value()) * slide_distance).round() as i32;
+        self.ui.set_offset(0, y_offset);
+
+        let chosen = chosen.or(self.ui.update(screen, input));
+        if let Some(answer) = chosen {
+            self.ui.set_root(self.question_widgets(Some(answer == self.correct_answer_position)));
         }
 
-        let mut correct = false;
-        draw_question(
-            screen,
-            input.mouse_position(),
-            input.is_mouse_up(MouseButton::Left),
-            &self.question,
-            &self.correct_answer,
-            &self.wrong_answers,
-            self.correct_answer_position,
-            &mut || correct = true,
-        );
-
-        if correct {
-            return Some(Box::new(TransitionState::new(Box::new(MainState::new()), None)));
+        screen.set_layer(LayerId::Ui);
+        self.ui.draw(screen);
+
+        if chosen == Some(self.correct_answer_position) {
+            // The phase only advances while the day is running, so it's also the time taken
+            ctx.progress.complete(DAY.day, None, None, self.phase);
+            return Some(StateChange::Replace(Box::new(TransitionState::back())));
         }
 
         None
     }
 
-    fn exit(&mut self, screen: &mut Screen, input: &mut Input) {
+    fn exit(&mut self, screen: &mut Screen, input: &mut Input, _ctx: &mut Context) {
     }
-}
 
-fn create_particles() -> Vec<Particle> {
-    let mut particles = Vec::new();
-    for i in 0..3 {
-        particles.push(Particle {
-            x: i as f64 * 2.0,
-            y: i as f64 * 2.0,
-            sprite: 'Z',
-        });
+    fn entry_transition(&self) -> TransitionConfig {
+        TransitionConfig::new(TransitionEffect::Dissolve)
+            .duration(Duration::from_millis(1500))
+            .easing(Easing::QuadInOut)
     }
-    particles
 }
 
-fn draw_particles(screen: &mut Screen, particles: &mut Vec<Particle>, x:u16, y:u16, phase: f64, dt: f64) {
-    let particle_amplitude = 4.0;
-    for (_i, particle) in particles.iter_mut().enumerate() {
-        particle.x += (phase * 2.0 + particle.y).sin() * particle_amplitude * dt;
-        screen.set_cell(x + particle.x as u16, y + particle.y as u16, particle.sprite, crossterm::style::Color::White);
-    }
+fn create_sleeping_zs(screen: &Screen) -> Emitter {
+    Emitter::new(screen.width(), screen.height())
+        .area(3.0, 0.0)
+        .rate(0.8)
+        .speed(0.6, 1.0)
+        .angle(-FRAC_PI_2 - 0.3, -FRAC_PI_2 + 0.3)
+        .lifetime(3.0, 4.0)
+        .sway(1.5, 2.0)
+        .wind(0.3)
+        .sprites(&Z_SPRITES)
+        .colors(fading_colors(&[screen.theme().text], screen.theme()))
 }
 
 pub const LAZY_CAT: &str = r#"
@@ -164,4 +229,56 @@ const TREE_FIREPLACE: &str = r#"
 /*    o   _..~~`'*   o\           ( (_)  )
 `-.__.~'`'   *   ___.-'            `----'
       ":-------:"
-        \_____/  "#;
\ No newline at end of file
+        \_____/  "#;
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use crate::testing::{date, Harness};
+    use super::{Day1State, DAY};
+
+    // Number keys for the answers, in the order they're shown
+    fn answer_keys(harness: &Harness) -> Vec<(KeyCode, &'static str)> {
+        let mut answers: Vec<(u16, &str)> = ["24", "42", "69"]
+            .iter()
+            .map(|answer| (harness.find(answer).unwrap().0, *answer))
+            .collect();
+        answers.sort();
+        answers
+            .into_iter()
+            .enumerate()
+            .map(|(i, (_, answer))| (KeyCode::Char(char::from_digit(i as u32 + 1, 10).unwrap()), answer))
+            .collect()
+    }
+
+    #[test]
+    fn asks_the_question_with_a_button_per_answer() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(Day1State::new()));
+        harness.idle(2);
+
+        assert!(harness.text().contains("What is the answer to life, the universe, and everything?"));
+        assert_eq!(answer_keys(&harness).len(), 3);
+    }
+
+    #[test]
+    fn a_wrong_answer_says_so_and_leaves_the_day_open() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(Day1State::new()));
+        harness.idle(2);
+
+        let (key, _) = answer_keys(&harness).into_iter().find(|(_, answer)| *answer != "42").unwrap();
+        harness.key(key);
+
+        assert!(harness.text().contains("Wrong!"));
+        assert!(!harness.ctx.progress.is_completed(DAY.day));
+    }
+
+    #[test]
+    fn the_right_answer_completes_the_day() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(Day1State::new()));
+        harness.idle(2);
+
+        let (key, _) = answer_keys(&harness).into_iter().find(|(_, answer)| *answer == "42").unwrap();
+        harness.key(key);
+
+        assert!(harness.ctx.progress.is_completed(DAY.day));
+    }
+}
@@ -1,48 +1,68 @@
-use crate::input::{Input, MouseButton};
+use crate::context::Context;
+use std::time::Duration;
+use crate::easing::Easing;
+use crate::transition::{TransitionConfig, TransitionEffect};
+use crossterm::event::KeyCode;
+use crate::input::Input;
 use crate::screen::Screen;
-use crate::state_machine::State;
-use rand::{thread_rng, Rng};
-use crate::drawing::{draw_ascii, draw_question, draw_text_box};
-use crate::input;
-use crate::states::main_state::MainState;
-use crate::states::transition_state::TransitionState;
+use crate::layer::LayerId;
+use crate::state_machine::{State, StateChange, DEFAULT_MIN_SIZE};
+use crate::drawing::{draw_ascii, text_width};
+use crate::states::pause_state::PauseState;
+use crate::days::{DayEntry, DayKind};
+use crate::widgets::label::Label;
+use crate::widgets::panel::Panel;
+use crate::widgets::ui::Ui;
+
+pub const DAY: DayEntry = DayEntry {
+    day: 24,
+    title: "Julaften",
+    description: "Se hvem som har kommet på besøk",
+    kind: DayKind::Showcase,
+    create: || Box::new(Day24State::new()),
+};
 
 pub struct Day24State {
-    title_text: String,
+    title: Ui,
 }
 
 impl Day24State {
     pub fn new() -> Self {
-        let title_text = "Behold! Our Father Christmas!".to_string();
-        Day24State { title_text }
+        let mut title = Ui::new(Panel::new(Label::new("Behold! Our Father Christmas!")).padding(2, 0));
+        title.set_offset(0, -24);
+        Day24State { title }
     }
 }
 
 impl State for Day24State {
-    fn enter(&mut self, screen: &mut Screen, input: &mut Input) {  }
+    fn enter(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context) {  }
 
-    fn update(&mut self, screen: &mut Screen, input: &mut Input, dt: f64) -> Option<Box<dyn State>> {
+    fn update(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, dt: f64) -> Option<StateChange> {
 
-        draw_ascii(screen, FATHER_CHRISTMAS, 50, screen.height() - 51);
-        draw_text_box(
-            screen,
-            screen.width(),
-            screen.height(),
-            &self.title_text,
-            0,
-            -25,
-            (0, 0),
-            false,
-        );
+        let x = (screen.width() - text_width(FATHER_CHRISTMAS.lines().nth(1).unwrap_or(""))) / 2;
+        draw_ascii(screen, FATHER_CHRISTMAS, x, screen.height() - 51);
+        screen.set_layer(LayerId::Ui);
+        self.title.draw(screen);
 
-        if input.is_key_up('q') {
-            return Some(Box::new(TransitionState::new(Box::new(MainState::new()), None)));
+        if input.is_key_pressed(KeyCode::Esc) || input.is_key_pressed('p') {
+            return Some(StateChange::Push(Box::new(PauseState::new())));
         }
 
         None
     }
 
-    fn exit(&mut self, screen: &mut Screen, input: &mut Input) {
+    fn exit(&mut self, screen: &mut Screen, input: &mut Input, _ctx: &mut Context) {
+    }
+
+    fn entry_transition(&self) -> TransitionConfig {
+        TransitionConfig::new(TransitionEffect::Crossfade)
+            .duration(Duration::from_secs(3))
+            .easing(Easing::SineInOut)
+    }
+
+    // Wide enough for the whole portrait, the top of it may be cut off on short screens
+    fn min_size(&self) -> (i32, i32) {
+        (100, DEFAULT_MIN_SIZE.1)
     }
 }
 
@@ -1,12 +1,35 @@
-use rand::prelude::IndexedRandom;
+use crate::context::Context;
+use crate::easing::Easing;
+use crate::transition::{TransitionConfig, TransitionEffect};
 use rand::Rng;
 use rand::seq::SliceRandom;
-use crate::drawing::{draw_ascii, draw_text_box};
-use crate::input::{Input, MouseButton};
+use crossterm::event::KeyCode;
+use crossterm::style::{Attribute, Color};
+use crate::drawing::draw_ascii;
+use crate::input::Input;
 use crate::screen::Screen;
-use crate::state_machine::State;
-use crate::states::main_state::MainState;
+use crate::layer::LayerId;
+use crate::state_machine::{State, StateChange};
+use crate::states::pause_state::PauseState;
 use crate::states::transition_state::TransitionState;
+use crate::timestep::FixedTimestep;
+use crate::particles::{fading_colors, Emitter, GroundCollision};
+use crate::days::{DayEntry, DayKind};
+use crate::widgets::button::Button;
+use crate::widgets::grid::Grid;
+use crate::widgets::label::Label;
+use crate::widgets::layout::VBox;
+use crate::widgets::panel::Panel;
+use crate::widgets::progress_bar::ProgressBar;
+use crate::widgets::ui::Ui;
+
+pub const DAY: DayEntry = DayEntry {
+    day: 2,
+    title: "Memory",
+    description: "Finn to og to like brikker til brettet er tomt",
+    kind: DayKind::Puzzle,
+    create: || Box::new(Day2State::new()),
+};
 
 struct Piece {
     x: u16,
@@ -14,41 +37,112 @@ struct Piece {
     sprite: char,
 }
 
-struct Particle {
-    x: f64,
-    y: f64,
-    speed: f64,
-    angle: f64,
-    sprite: char,
-}
+const BOARD_SIZE: u16 = 4;
+const PIECE_COUNT: usize = (BOARD_SIZE * BOARD_SIZE) as usize;
+
+// Board slots are numbered row by row, the back button comes after all of them
+const BACK_BUTTON: usize = 100;
+
+const CONFETTI_SPRITES: [char; 10] = ['.', ',', '\'', '`', '^', '"', '*', 'o', 'O', '@'];
 
 pub struct Day2State {
     pieces: Vec<Piece>,
     selected: Vec<usize>,
     moves: u32,
-    confetti: Vec<Particle>,
+    confetti: Emitter,
+    ui: Ui,
+    time: f64,
+    completed: bool,
+    timestep: FixedTimestep,
 }
 
 impl Day2State {
     pub fn new() -> Self {
         Day2State {
-            pieces: create_pieces(),
+            pieces: vec![],
             selected: vec![],
             moves: 0,
-            confetti: vec![],
+            confetti: Emitter::new(0, 0),
+            ui: Ui::new(Label::new("")),
+            time: 0.0,
+            completed: false,
+            timestep: FixedTimestep::default(),
+        }
+    }
+
+    fn select_piece(&mut self, i: usize) {
+        if self.selected.contains(&i) {
+            return;
+        }
+
+        if self.selected.len() == 2 {
+            self.selected.clear();
+        }
+
+        self.selected.push(i);
+
+        if self.selected.len() == 2 {
+            self.moves += 1;
+        }
+    }
+
+    // Keeps slots where pieces were removed as empty cells, so the board doesn't shift around
+    fn board_widgets(&self) -> VBox {
+        let pairs_found = (PIECE_COUNT - self.pieces.len()) / 2;
+        let header = VBox::new()
+            .spacing(1)
+            .child(Panel::new(Label::new("Finn to like brikker og klikk på dem for å fjerne dem")).padding(2, 0))
+            .child(Panel::new(Label::new("Målet er å fjerne alle brikkene")).padding(2, 0));
+        let footer = VBox::new()
+            .spacing(1)
+            .child(ProgressBar::new(pairs_found as f64 / (PIECE_COUNT / 2) as f64, 16).label(format!("{}/{} par", pairs_found, PIECE_COUNT / 2)))
+            .child(Button::new(BACK_BUTTON, "Tilbake"));
+
+        // The cleared board makes way for the result
+        if self.pieces.is_empty() {
+            let result = Label::new(format!("Gratulerer! Du klarte det på {} trekk!", self.moves)).attributes(Attribute::Bold.into());
+            return VBox::new()
+                .spacing(1)
+                .child(header)
+                .child(Panel::new(result).padding(2, 0).background(Color::DarkGreen))
+                .child(footer);
+        }
+
+        let mut board = Grid::new(BOARD_SIZE as usize).gap(2, 1);
+        for slot in 0..PIECE_COUNT {
+            let (x, y) = (slot as u16 % BOARD_SIZE, slot as u16 / BOARD_SIZE);
+            let Some(i) = self.pieces.iter().position(|piece| piece.x == x && piece.y == y) else {
+                board = board.child(Label::new(""));
+                continue;
+            };
+
+            let piece = if self.selected.contains(&i) {
+                Button::new(slot, self.pieces[i].sprite.to_string())
+                    .background(Color::DarkRed)
+                    .attributes(Attribute::Bold.into())
+            } else {
+                // As wide as a sprite, so pieces keep their size when turned over
+                Button::new(slot, " ")
+            };
+            board = board.child(piece);
         }
+
+        VBox::new()
+            .spacing(1)
+            .child(header)
+            .child(board)
+            .child(footer)
     }
 }
 
-fn create_pieces() -> Vec<Piece> {
-    let mut rng = rand::thread_rng();
+fn create_pieces(rng: &mut impl Rng) -> Vec<Piece> {
     let mut pieces = vec![];
 
     let mut sprites = vec!['α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ', 'α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ'];
-    sprites.shuffle(&mut rng);
+    sprites.shuffle(rng);
 
-    for x in 0..4 {
-        for y in 0..4 {
+    for x in 0..BOARD_SIZE {
+        for y in 0..BOARD_SIZE {
             let sprite = sprites.pop().unwrap();
             pieces.push(Piece { x, y, sprite });
         }
@@ -57,54 +151,53 @@ fn create_pieces() -> Vec<Piece> {
     pieces
 }
 
-fn create_confetti(width: u16, height: u16) -> Vec<Particle> {
-    let mut rng = rand::thread_rng();
-    let mut confetti = vec![];
-
-    for _ in 0..100 {
-        let x = width as f64 / 2.0;
-        let y = height as f64 / 2.0;
-        let speed = rng.gen_range(1.0..30.0);
-        let angle = rng.gen_range(0.0..std::f64::consts::PI * 2.0);
-        let sprite = ['.', ',', '\'', '`', '^', '"', '*', 'o', 'O', '@']
-            .choose(&mut rng)
-            .unwrap()
-            .clone();
-        confetti.push(Particle { x, y, speed, angle, sprite });
-    }
-
-    confetti
+// Bursts out from the middle of the screen and fades as it falls
+fn create_confetti(screen: &Screen) -> Emitter {
+    let theme = screen.theme();
+    Emitter::new(screen.width(), screen.height())
+        .position(screen.width() as f64 / 2.0, screen.height() as f64 / 2.0)
+        .rate(60.0)
+        .speed(1.0, 30.0)
+        .angle(0.0, std::f64::consts::TAU)
+        .lifetime(1.5, 3.0)
+        .gravity(12.0)
+        .sprites(&CONFETTI_SPRITES)
+        .colors(fading_colors(&[theme.star, theme.text], theme))
+        .ground(GroundCollision::Bounce(0.5))
 }
 
 impl State for Day2State {
-    fn enter(&mut self, screen: &mut Screen, input: &mut Input) {
-        self.confetti = create_confetti(screen.width(), screen.height());
+    fn enter(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context) {
+        self.pieces = create_pieces(&mut ctx.rng);
+        self.confetti = create_confetti(screen);
+        self.ui.set_root(self.board_widgets());
     }
 
-    fn update(&mut self, screen: &mut Screen, input: &mut Input, dt: f64) -> Option<Box<dyn State>> {
+    fn update(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, dt: f64) -> Option<StateChange> {
 
-        if let Some((width, height)) = input.resized() {
-            self.confetti = create_confetti(width, height);
+        if input.resized().is_some() {
+            self.confetti.resize(screen.width(), screen.height());
+            self.confetti.set_position(screen.width() as f64 / 2.0, screen.height() as f64 / 2.0);
         }
 
-        let santa_y = (screen.height() as f64 / 2.0 - 20.0).clamp(0.0, screen.height() as f64 - 40.0) as u16;
+        let santa_y = (screen.height() / 2 - 20).min(screen.height() - 40).max(0);
         draw_ascii(screen, SANTA, screen.width() - 50, santa_y);
 
-        let new_selected = draw_boxes(screen, input, &self.pieces, &self.selected);
-        if new_selected.len() > 0 && input.is_mouse_up(MouseButton::Left) {
-            let i = new_selected.first().unwrap_or(&0).clone();
-
-            if !self.selected.contains(&i) {
-                if self.selected.len() == 2 {
-                    self.selected.clear();
-                }
-
-                self.selected.push(i);
+        if input.is_key_pressed(KeyCode::Esc) || input.is_key_pressed('p') {
+            return Some(StateChange::Push(Box::new(PauseState::new())));
+        }
 
-                if self.selected.len() == 2 {
-                    self.moves += 1;
+        screen.set_layer(LayerId::Ui);
+        match self.ui.update(screen, input) {
+            Some(BACK_BUTTON) => return Some(StateChange::Replace(Box::new(TransitionState::back()))),
+            Some(slot) => {
+                let (x, y) = (slot as u16 % BOARD_SIZE, slot as u16 / BOARD_SIZE);
+                if let Some(i) = self.pieces.iter().position(|piece| piece.x == x && piece.y == y) {
+                    self.select_piece(i);
+                    self.ui.set_root(self.board_widgets());
                 }
             }
+            None => {}
         }
 
         if self.selected.len() == 2 {
@@ -120,120 +213,40 @@ impl State for Day2State {
                     self.pieces.remove(second);
                 }
                 self.selected.clear();
+                self.ui.set_root(self.board_widgets());
             }
         }
 
-        if self.pieces.len() == 0 {
-            draw_win(screen, dt, &mut self.confetti, self.moves);
+        if !self.completed {
+            self.time += dt;
         }
 
-        let explanation1 = "Finn to like brikker og klikk på dem for å fjerne dem";
-        let explanation2 = "Målet er å fjerne alle brikkene";
-        draw_text_box(
-            screen,
-            screen.width(),
-            screen.height(),
-            explanation1,
-            0,
-            -16,
-            (0, 0),
-            false,
-        );
-        draw_text_box(
-            screen,
-            screen.width(),
-            screen.height(),
-            explanation2,
-            0,
-            -13,
-            (0, 0),
-            false,
-        );
-
-        let exit = draw_text_box(
-            screen,
-            screen.width(),
-            screen.height(),
-            "Tilbake",
-            0,
-            12,
-            input.mouse_position(),
-            input.is_mouse_up(MouseButton::Left),
-        );
-        if exit && input.is_mouse_up(MouseButton::Left) {
-            return Some(Box::new(TransitionState::new(Box::new(MainState::new()), None)));
+        if self.pieces.is_empty() && !self.completed {
+            self.completed = true;
+            ctx.progress.complete(DAY.day, None, Some(self.moves), self.time);
         }
 
-        None
-    }
-
-    fn exit(&mut self, screen: &mut Screen, input: &mut Input) {
-    }
-}
+        self.ui.draw(screen);
 
-fn draw_boxes(screen: &mut Screen, input: &mut Input, pieces: &Vec<Piece>, selected: &Vec<usize>) -> Vec<usize> {
-    let num_pieces = 16;
-    let box_size = 9;
-    let box_height = 4;
-    let row_size = 4;
-    let x_offset = -((num_pieces / row_size) * 6 / 2);
-    let y_offset = -((num_pieces / row_size) * box_height / 2);
-
-    let mut new_selected = vec![];
-
-    for (i, piece) in pieces.iter().enumerate() {
-        let x = piece.x as i16 * box_size + x_offset;
-        let y = piece.y as i16 * box_height + y_offset;
-
-        let str = if selected.contains(&i) {
-            piece.sprite.to_string()
-        } else {
-            "  ".to_string()
-        };
-
-        let hovered = draw_text_box(
-            screen,
-            screen.width(),
-            screen.height(),
-            &str,
-            x,
-            y,
-            input.mouse_position(),
-            input.is_mouse_up(MouseButton::Left),
-        );
-
-        if hovered {
-            new_selected.push(i);
+        if self.pieces.is_empty() {
+            for _ in 0..self.timestep.advance(dt) {
+                self.confetti.update(&mut ctx.rng, self.timestep.step());
+            }
+            screen.set_layer(LayerId::Particles);
+            self.confetti.draw(screen, self.timestep.alpha());
         }
-    }
-
-    new_selected
-}
-
-fn draw_win(screen: &mut Screen, dt: f64, confetti: &mut Vec<Particle>, moves: u32) {
-    for particle in confetti.iter_mut() {
-        particle.x += particle.speed * particle.angle.cos() * dt;
-        particle.y += particle.speed * particle.angle.sin() * dt;
 
-        if particle.x < 0.0 || particle.x >= screen.width() as f64 || particle.y < 0.0 || particle.y >= screen.height() as f64 {
-            particle.x = screen.width() as f64 / 2.0;
-            particle.y = screen.height() as f64 / 2.0;
-        }
+        None
+    }
 
-        screen.set_cell(particle.x as u16, particle.y as u16, particle.sprite, crossterm::style::Color::White);
+    fn exit(&mut self, screen: &mut Screen, input: &mut Input, _ctx: &mut Context) {
     }
 
-    let str = format!("Gratulerer! Du klarte det på {} trekk!", moves);
-    draw_text_box(
-        screen,
-        screen.width(),
-        screen.height(),
-        &str,
-        0,
-        0,
-        (0, 0),
-        false,
-    );
+    fn entry_transition(&self) -> TransitionConfig {
+        TransitionConfig::new(TransitionEffect::SnowCurtain)
+            .easing(Easing::CubicOut)
+            .color(Color::Rgb { r: 220, g: 235, b: 255 })
+    }
 }
 
 const SANTA: &str = r#"
@@ -271,3 +284,98 @@ const SANTA: &str = r#"
 ⠀⠀⠀⠀⠈⠛⢿⣧⣄⡀⠘⣆⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠘⣧⠀⢻⣦⡀⠀⠀⠀⠀⠀⠀⠀⠀⠀⢀⠏⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀
 ⠀⠀⠀⠀⠀⠀⠀⠈⠙⠻⠶⠿⣶⣄⣀⠀⠀⠀⠀⠀⣀⣤⠞⠙⢷⣄⡙⠻⣶⣤⣀⡀⠀⠀⢀⣠⡴⠋⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀
 ⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⣙⡻⠿⣿⠿⠿⠿⠛⠁⠀⠀⠀⠈⣙⠛⠒⢛⠛⠛⠛⠛⠉⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀⠀"#;
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use crossterm::style::{Attribute, Color};
+    use crate::drawing::text_width;
+    use crate::states::main_state::MainState;
+    use crate::testing::{date, Harness};
+    use super::{Day2State, DAY};
+
+    const SPRITES: [char; 8] = ['α', 'β', 'γ', 'δ', 'ε', 'ζ', 'η', 'θ'];
+
+    #[test]
+    fn the_board_starts_face_down() {
+        let mut harness = Harness::with_state(date(2024, 12, 2), Box::new(Day2State::new()));
+        harness.idle(2);
+
+        let text = harness.text();
+        assert!(text.contains("0/8 par"));
+        assert!(text.contains("Tilbake"));
+        assert!(!text.contains(SPRITES));
+    }
+
+    #[test]
+    fn a_selected_piece_shows_its_sprite_highlighted() {
+        let mut harness = Harness::with_state(date(2024, 12, 2), Box::new(Day2State::new()));
+        harness.idle(2);
+
+        harness.key(KeyCode::Right);
+        harness.key(KeyCode::Enter);
+
+        let shown: Vec<_> = harness.memory.cells().into_iter().filter(|cell| SPRITES.contains(&cell.rune)).collect();
+        assert_eq!(shown.len(), 1);
+        assert_eq!(shown[0].background, Some(Color::DarkRed));
+        assert!(shown[0].attributes.has(Attribute::Bold));
+    }
+
+    // Middles of the face down pieces, row by row
+    fn piece_positions(harness: &Harness) -> Vec<(u16, u16)> {
+        let mut positions = Vec::new();
+        for (y, line) in harness.text().lines().enumerate() {
+            for (byte, _) in line.match_indices("╭─────╮") {
+                positions.push((text_width(&line[..byte]) as u16 + 3, y as u16 + 1));
+            }
+        }
+        positions
+    }
+
+    fn sprite_at(harness: &Harness, (x, y): (u16, u16)) -> char {
+        harness.memory.cell(x, y).unwrap().rune
+    }
+
+    #[test]
+    fn clicking_day_2_and_matching_every_pair_wins() {
+        let mut harness = Harness::with_state(date(2024, 12, 2), Box::new(MainState::new()));
+        harness.idle(2);
+
+        let door = harness.find("02").unwrap();
+        harness.click(door);
+        // Covering the calendar and revealing the day take two seconds each
+        harness.idle(300);
+        assert!(harness.text().contains("0/8 par"));
+
+        let positions = piece_positions(&harness);
+        assert_eq!(positions.len(), 16);
+
+        // Turn over two at a time to learn where everything is, a lucky pair goes away at once
+        let mut sprites = vec![None; positions.len()];
+        for i in (0..positions.len()).step_by(2) {
+            harness.click(positions[i]);
+            sprites[i] = Some(sprite_at(&harness, positions[i]));
+
+            harness.click(positions[i + 1]);
+            let second = sprite_at(&harness, positions[i + 1]);
+            sprites[i + 1] = if second == ' ' { None } else { Some(second) };
+            if sprites[i + 1].is_none() {
+                sprites[i] = None;
+            }
+        }
+
+        for i in 0..positions.len() {
+            let Some(sprite) = sprites[i] else { continue };
+            let j = (i + 1..positions.len()).find(|&j| sprites[j] == Some(sprite)).unwrap();
+            harness.click(positions[i]);
+            harness.click(positions[j]);
+            sprites[i] = None;
+            sprites[j] = None;
+        }
+
+        let text = harness.text();
+        assert!(text.contains("8/8 par"));
+        assert!(text.contains("Gratulerer! Du klarte det på"));
+        assert!(harness.ctx.progress.is_completed(DAY.day));
+    }
+}
@@ -0,0 +1,70 @@
+use crate::context::Context;
+use crossterm::event::KeyCode;
+use crate::input::{Input, MouseButton};
+use crate::layer::LayerId;
+use crate::screen::Screen;
+use crate::state_machine::{State, StateChange};
+use crate::widgets::list::List;
+use crate::widgets::panel::Panel;
+use crate::widgets::ui::Ui;
+
+const HELP_TEXT: [&str; 10] = [
+    "Hjelp",
+    "",
+    "Piltaster / scroll   Flytt mellom valg",
+    "Tab / Shift+Tab      Neste / forrige valg",
+    "Enter / mellomrom    Velg",
+    "Mus                  Pek og klikk",
+    "Esc / p              Pause",
+    "h                    Vis denne hjelpen",
+    "q                    Avslutt",
+    "",
+];
+
+// Overlay with the controls, the state below keeps running underneath
+pub struct HelpState {
+    ui: Ui,
+}
+
+const HELP_LIST: usize = 0;
+
+impl HelpState {
+    pub fn new() -> Self {
+        // Scrolls with the arrows or the wheel when the terminal is too short for all of it
+        let lines = HELP_TEXT.iter().map(|line| line.to_string()).collect();
+        let mut ui = Ui::new(Panel::new(List::new(lines).focusable(HELP_LIST)).padding(2, 1));
+        ui.focus(Some(HELP_LIST));
+        HelpState { ui }
+    }
+}
+
+impl State for HelpState {
+    fn enter(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+    }
+
+    fn update(&mut self, screen: &mut Screen, input: &mut Input, _ctx: &mut Context, _dt: f64) -> Option<StateChange> {
+        screen.set_layer(LayerId::Ui);
+        let chosen = self.ui.update(screen, input);
+        self.ui.draw(screen);
+
+        if input.is_key_pressed(KeyCode::Esc)
+            || input.is_key_pressed('h')
+            || chosen.is_some()
+            || input.is_mouse_up(MouseButton::Left) {
+            return Some(StateChange::Pop);
+        }
+
+        None
+    }
+
+    fn exit(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+    }
+
+    fn is_transparent(&self) -> bool {
+        true
+    }
+
+    fn updates_below(&self) -> bool {
+        true
+    }
+}
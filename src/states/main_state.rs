@@ -1,94 +1,216 @@
-use crate::drawing::{draw_ascii, draw_calendar, draw_ground};
-use crate::state_machine::State;
+use crate::animation::{Animation, Lerp};
+use crate::context::Context;
+use crate::calendar::DoorState;
+use crate::drawing::{calendar_widgets, come_back_text, draw_ascii, draw_ground, CALENDAR_DAYS};
+use crate::easing::Easing;
+use crate::state_machine::{State, StateChange};
 use crate::screen::Screen;
-use crate::{ascii, snowflakes, states};
+use crate::layer::LayerId;
+use crate::{ascii, days, states};
+use rand::Rng;
 use crate::input::{Input, MouseButton};
-use crate::snowflakes::Snowflake;
+use crate::snowflakes::Snowfall;
+use crate::timestep::FixedTimestep;
+use crossterm::style::Color;
+use crate::widgets::label::Label;
+use crate::widgets::panel::Panel;
+use crate::widgets::ui::Ui;
+
+const SANTA_X: i32 = 2;
+const SANTA_HEIGHT: i32 = 20;
+const SYSTEK_HALF_WIDTH: i32 = 32;
+const SYSTEK_Y: i32 = 1;
 
 pub struct MainState {
-    snowflakes: Vec<Snowflake>,
-    phase: f64,
-    prev_width: u16,
-    prev_height: u16,
+    snowfall: Option<Snowfall>,
+    timestep: FixedTimestep,
+    prev_width: i32,
+    prev_height: i32,
+    calendar: Ui,
+    // The door the line under the calendar is about, the hovered one or else the focused one
+    described_day: Option<usize>,
+    // Fades in and out after trying to open a locked door
+    message: Option<(String, Animation<f64>)>,
 }
 
 impl MainState {
     pub fn new() -> MainState {
         MainState {
-            snowflakes: Vec::new(),
-            phase: 0.0,
+            snowfall: None,
+            timestep: FixedTimestep::default(),
             prev_width: 0,
             prev_height: 0,
+            calendar: Ui::new(Label::new("")),
+            described_day: None,
+            message: None,
         }
     }
 }
 
 impl State for MainState {
-    fn enter(&mut self, screen: &mut Screen, input: &mut Input) {
+    fn enter(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context) {
         self.prev_width = screen.width();
         self.prev_height = screen.height();
-        self.snowflakes = snowflakes::create(screen.width(), screen.height());
+        self.snowfall = Some(create_snowfall(&mut ctx.rng, screen));
+        self.rebuild_calendar(screen, ctx);
+    }
+
+    // Coming back from a day may have finished it
+    fn resume(&mut self, screen: &mut Screen, _input: &mut Input, ctx: &mut Context) {
+        self.rebuild_calendar(screen, ctx);
     }
 
-    fn update(&mut self, screen: &mut Screen, input: &mut Input, dt: f64) -> Option<Box<dyn State>> {
+    fn update(&mut self, screen: &mut Screen, input: &mut Input, ctx: &mut Context, dt: f64) -> Option<StateChange> {
         let screen_height = screen.height();
         let screen_width = screen.width();
 
         if self.prev_width != screen_width || self.prev_height != screen_height {
             self.prev_width = screen_width;
             self.prev_height = screen_height;
-            self.snowflakes = snowflakes::create(screen_width, screen_height);
+            self.snowfall = Some(create_snowfall(&mut ctx.rng, screen));
         }
 
-        self.phase += dt;
-
-        snowflakes::update(&mut self.snowflakes, screen_width, screen_height, self.phase, dt);
-        if input.is_mouse_down(MouseButton::Left) {
-            snowflakes::spawn_mouse_snow_flakes(&mut self.snowflakes, input.mouse_position())
+        let snowfall = self.snowfall.get_or_insert_with(|| create_snowfall(&mut ctx.rng, screen));
+        snowfall.push(input.mouse_position(), input.mouse_delta(), input.is_mouse_down(MouseButton::Left));
+        for _ in 0..self.timestep.advance(dt) {
+            snowfall.update(&mut ctx.rng, self.timestep.step());
         }
 
-        draw_ascii(screen, ascii::SANTA, 2, screen_height - 20);
-        snowflakes::draw(screen, &self.snowflakes);
-        draw_ascii(screen, ascii::SYSTEK, screen_width / 2 - 32, 1);
+        draw_ascii(screen, ascii::SANTA, SANTA_X, screen_height - SANTA_HEIGHT);
+        draw_ascii(screen, ascii::SYSTEK, screen_width / 2 - SYSTEK_HALF_WIDTH, SYSTEK_Y);
+        snowfall.draw(screen, self.timestep.alpha());
         draw_ground(screen);
 
-        if let Some(ref day) = draw_calendar(screen, input.mouse_position(), input.is_mouse_up(MouseButton::Left)) {
-            let next: Option<Box<dyn State>> =  match day {
-                1 => Some(Box::new(states::day1_state::Day1State::new())),
-                2 => Some(Box::new(states::day2_state::Day2State::new())),
-                // 3 => Some(Box::new(states::day3_state::Day3State::new())),
-                // 4 => Some(Box::new(states::day4_state::Day4State::new())),
-                // 5 => Some(Box::new(states::day5_state::Day5State::new())),
-                // 6 => Some(Box::new(states::day6_state::Day6State::new())),
-                // 7 => Some(Box::new(states::day7_state::Day7State::new())),
-                // 8 => Some(Box::new(states::day8_state::Day8State::new())),
-                // 9 => Some(Box::new(states::day9_state::Day9State::new())),
-                // 10 => Some(Box::new(states::day10_state::Day10State::new())),
-                // 11 => Some(Box::new(states::day11_state::Day11State::new())),
-                // 12 => Some(Box::new(states::day12_state::Day12State::new())),
-                // 13 => Some(Box::new(states::day13_state::Day13State::new())),
-                // 14 => Some(Box::new(states::day14_state::Day14State::new())),
-                // 15 => Some(Box::new(states::day15_state::Day15State::new())),
-                // 16 => Some(Box::new(states::day16_state::Day16State::new())),
-                // 17 => Some(Box::new(states::day17_state::Day17State::new())),
-                // 18 => Some(Box::new(states::day18_state::Day18State::new())),
-                // 19 => Some(Box::new(states::day19_state::Day19State::new())),
-                // 20 => Some(Box::new(states::day20_state::Day20State::new())),
-                // 21 => Some(Box::new(states::day21_state::Day21State::new())),
-                // 22 => Some(Box::new(states::day22_state::Day22State::new())),
-                // 23 => Some(Box::new(states::day23_state::Day23State::new())),
-                24 => Some(Box::new(states::day24_state::Day24State::new())),
-                _ => None,
-            };
-
-            if next.is_some() {
-                return Some(Box::new(states::transition_state::TransitionState::new(next.unwrap(), None)));
+        if input.is_key_pressed('h') {
+            return Some(StateChange::Push(Box::new(states::help_state::HelpState::new())));
+        }
+
+        screen.set_layer(LayerId::Ui);
+        let opened_day = self.calendar.update(screen, input);
+
+        let described_day = self.calendar.hovered().or(self.calendar.focused());
+        if described_day != self.described_day {
+            self.described_day = described_day;
+            self.rebuild_calendar(screen, ctx);
+        }
+        self.calendar.draw(screen);
+
+        if let Some((text, fade)) = &mut self.message {
+            fade.update(dt);
+            if fade.is_finished() {
+                self.message = None;
+            } else {
+                let color = Color::lerp(screen.theme().background, screen.theme().text, fade.value());
+                let mut message_box = Ui::new(Panel::new(Label::new(text.as_str()).color(color)).padding(2, 0).color(color));
+                message_box.set_offset(0, 15);
+                message_box.draw(screen);
+            }
+        }
+
+        if let Some(day) = opened_day {
+            if let DoorState::Locked { days_left } = ctx.door_state(day) {
+                let text = format!("Luke {} er låst, {}!", day, come_back_text(days_left));
+                let fade = Animation::new(0.0)
+                    .to(1.0, 0.3, Easing::QuadOut)
+                    .delay(2.4)
+                    .to(0.0, 0.3, Easing::QuadIn);
+                self.message = Some((text, fade));
+                return None;
+            }
+
+            if let Some(entry) = days::find(day) {
+                ctx.progress.mark_opened(day);
+                let next = (entry.create)();
+                return Some(StateChange::Push(Box::new(states::transition_state::TransitionState::new(next))));
             }
         }
 
         None
     }
 
-    fn exit(&mut self, screen: &mut Screen, input: &mut Input) {
+    fn exit(&mut self, screen: &mut Screen, input: &mut Input, _ctx: &mut Context) {
+    }
+}
+
+impl MainState {
+    fn rebuild_calendar(&mut self, screen: &Screen, ctx: &Context) {
+        let doors: Vec<DoorState> = (1..=CALENDAR_DAYS).map(|day| ctx.door_state(day)).collect();
+        self.calendar.set_root(calendar_widgets(screen.theme(), self.described_day, &doors, &ctx.progress));
     }
-}
\ No newline at end of file
+}
+
+// Snow settles on the same art that `update` draws, so they have to agree on where it is
+fn create_snowfall(rng: &mut impl Rng, screen: &Screen) -> Snowfall {
+    let mut snowfall = Snowfall::new(rng, screen);
+    snowfall.settle_on(ascii::SANTA, SANTA_X, screen.height() - SANTA_HEIGHT);
+    snowfall.settle_on(ascii::SYSTEK, screen.width() / 2 - SYSTEK_HALF_WIDTH, SYSTEK_Y);
+    snowfall
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use crate::event_source::resize;
+    use crate::testing::{date, Harness, SIZE};
+    use super::MainState;
+
+    #[test]
+    fn draws_every_door_and_locks_the_ones_still_to_come() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(MainState::new()));
+        harness.idle(2);
+
+        assert_eq!(harness.memory.size(), SIZE);
+        assert_eq!(harness.memory.cells().len(), SIZE.0 as usize * SIZE.1 as usize);
+
+        let text = harness.text();
+        for day in 1..=24 {
+            assert!(text.contains(&format!("{:02}", day)), "door {} is missing", day);
+        }
+
+        let theme = *harness.screen.theme();
+        let (x, y) = harness.find("01").unwrap();
+        assert_eq!(harness.memory.cell(x, y).unwrap().color, theme.text);
+        let (x, y) = harness.find("02").unwrap();
+        assert_eq!(harness.memory.cell(x, y).unwrap().color, theme.locked);
+    }
+
+    #[test]
+    fn opening_a_locked_door_says_when_to_come_back() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(MainState::new()));
+        harness.idle(2);
+
+        // The first arrow focuses door 1, the next one moves on to door 2
+        harness.key(KeyCode::Right);
+        harness.key(KeyCode::Right);
+        harness.key(KeyCode::Enter);
+        harness.idle(30);
+
+        assert!(harness.text().contains("Luke 2 er låst, kom tilbake i morgen!"));
+    }
+
+    #[test]
+    fn clicking_a_locked_door_with_nothing_behind_it_still_says_when_to_come_back() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(MainState::new()));
+        harness.idle(2);
+
+        let (x, y) = harness.find("05").unwrap();
+        harness.click((x, y));
+        harness.idle(30);
+
+        assert!(harness.text().contains("Luke 5 er låst, kom tilbake om 4 dager!"));
+    }
+
+    #[test]
+    fn the_calendar_follows_the_terminal_size() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(MainState::new()));
+        harness.idle(2);
+        let (x, _) = harness.find("01").unwrap();
+
+        harness.frame(vec![resize(100, 45)]);
+        harness.idle(1);
+
+        assert_eq!(harness.memory.size(), (100, 45));
+        assert_eq!(harness.find("01").unwrap().0, x - 10);
+        assert!(harness.text().contains("24"));
+    }
+}
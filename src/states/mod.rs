@@ -1,5 +1,11 @@
 pub mod transition_state;
 pub mod main_state;
-pub mod day1_state;
-pub mod day2_state;
-pub mod day24_state;
+pub mod pause_state;
+pub mod help_state;
+
+// The one place a day is added, its module has to define `pub const DAY: DayEntry`
+crate::days::register_days! {
+    day1_state,
+    day2_state,
+    day24_state,
+}
@@ -0,0 +1,130 @@
+use crate::animation::{Animation, Lerp};
+use crate::context::Context;
+use crate::easing::Easing;
+use crossterm::event::KeyCode;
+use crossterm::style::{Attribute, Color};
+use crate::input::Input;
+use crate::screen::Screen;
+use crate::layer::LayerId;
+use crate::state_machine::{State, StateChange};
+use crate::states::help_state::HelpState;
+use crate::states::transition_state::TransitionState;
+use crate::widgets::button::Button;
+use crate::widgets::label::Label;
+use crate::widgets::layout::VBox;
+use crate::widgets::panel::Panel;
+use crate::widgets::ui::Ui;
+
+const OPTIONS: [&str; 3] = ["Fortsett", "Hjelp", "Tilbake til kalenderen"];
+
+// Menu on top of a day, which stays visible but frozen underneath
+pub struct PauseState {
+    menu: Ui,
+    // Goes from 0 to 1 as the menu drops down from the top
+    slide_in: Animation<f64>,
+    // Blends the title between the text and hover colors
+    title_pulse: Animation<f64>,
+}
+
+impl PauseState {
+    pub fn new() -> Self {
+        PauseState {
+            menu: Ui::new(Label::new("")),
+            slide_in: Animation::new(0.0).to(1.0, 0.4, Easing::CubicOut),
+            title_pulse: Animation::new(0.0)
+                .to(1.0, 1.0, Easing::SineInOut)
+                .to(0.0, 1.0, Easing::SineInOut)
+                .looping(),
+        }
+    }
+}
+
+// The options are numbered by their place in OPTIONS
+fn menu_widgets(title_color: Color) -> VBox {
+    let options = OPTIONS
+        .iter()
+        .enumerate()
+        .fold(VBox::new().spacing(1), |column, (i, option)| column.child(Button::new(i, *option)));
+
+    let title = Label::new("Pause").color(title_color).attributes(Attribute::Bold.into());
+    VBox::new()
+        .spacing(2)
+        .child(Panel::new(title).padding(2, 0).color(title_color))
+        .child(options)
+}
+
+impl State for PauseState {
+    fn enter(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+        self.menu.focus(Some(0));
+        self.slide_in.restart();
+    }
+
+    fn update(&mut self, screen: &mut Screen, input: &mut Input, _ctx: &mut Context, dt: f64) -> Option<StateChange> {
+        self.slide_in.update(dt);
+        self.title_pulse.update(dt);
+        screen.set_layer(LayerId::Ui);
+
+        if input.is_key_pressed(KeyCode::Esc) || input.is_key_pressed('p') {
+            return Some(StateChange::Pop);
+        }
+
+        // Starts with the title just below the top row
+        let slide_distance = (screen.height() as f64 / 2.0 - 9.0).max(0.0);
+        let y_offset = -((1.0 - self.slide_in.value()) * slide_distance).round() as i32;
+        let title_color = Color::lerp(screen.theme().text, screen.theme().hover, self.title_pulse.value());
+
+        // Rebuilt every frame for the pulsing title, focus carries over
+        self.menu.set_root(menu_widgets(title_color));
+        self.menu.set_offset(0, y_offset);
+        let chosen = self.menu.update(screen, input);
+        self.menu.draw(screen);
+
+        match chosen {
+            Some(0) => Some(StateChange::Pop),
+            Some(1) => Some(StateChange::Push(Box::new(HelpState::new()))),
+            // Closes the menu and the day behind it
+            Some(2) => Some(StateChange::Batch(vec![
+                StateChange::Pop,
+                StateChange::Replace(Box::new(TransitionState::back())),
+            ])),
+            _ => None,
+        }
+    }
+
+    fn exit(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+    }
+
+    fn is_transparent(&self) -> bool {
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::KeyCode;
+    use crate::testing::{date, Harness};
+    use super::PauseState;
+
+    #[test]
+    fn the_arrows_pick_an_option_and_enter_chooses_it() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(PauseState::new()));
+        harness.idle(30);
+        assert!(harness.text().contains("Pause"));
+        assert!(harness.text().contains("Tilbake til kalenderen"));
+
+        harness.key(KeyCode::Down);
+        harness.key(KeyCode::Enter);
+        harness.idle(2);
+        assert!(harness.text().contains("Tab / Shift+Tab"));
+    }
+
+    #[test]
+    fn clicking_continue_closes_the_menu() {
+        let mut harness = Harness::with_state(date(2024, 12, 1), Box::new(PauseState::new()));
+        harness.idle(30);
+
+        let (x, y) = harness.find("Fortsett").unwrap();
+        harness.click((x, y));
+        assert!(harness.state_machine.is_empty());
+    }
+}
@@ -1,69 +1,125 @@
-use std::time::Duration;
+use crate::context::Context;
 use crate::input::Input;
-use crate::screen::{Cell, Screen};
-use crate::state_machine::State;
-use crate::transition::{Transition, TransitionDirection};
+use crate::canvas::Canvas;
+use crate::layer::LayerId;
+use crate::screen::Screen;
+use crate::state_machine::{State, StateChange};
+use crate::timestep::FixedTimestep;
+use crate::transition::{Transition, TransitionConfig, TransitionDirection, TransitionEffect, SNOW};
 
 pub struct TransitionState {
     next_state: Option<Box<dyn State>>,
     transition: Transition,
-    prev_cells: Vec<Cell>,
+    // The screen as it was when the transition started, drawn on its own layer
+    snapshot: Option<Canvas>,
+    timestep: FixedTimestep,
 }
 
 impl TransitionState {
-    pub fn new(next_state: Box<dyn State>, state: Option<TransitionDirection>) -> Self {
+    // Covers the current screen, then swaps itself for next_state and reveals it,
+    // using whichever transition next_state asks for
+    pub fn new(next_state: Box<dyn State>) -> Self {
+        let config = next_state.entry_transition();
+        TransitionState::with(Some(next_state), config, TransitionDirection::In)
+    }
+
+    // Covers the current screen, then pops itself to reveal the state below
+    pub fn back() -> Self {
+        let config = TransitionConfig::new(TransitionEffect::WipeLeft).pattern(SNOW);
+        TransitionState::with(None, config, TransitionDirection::In)
+    }
+
+    // Starts out covered and reveals the state below
+    pub fn reveal(config: TransitionConfig) -> Self {
+        TransitionState::with(None, config, TransitionDirection::Out)
+    }
+
+    fn with(next_state: Option<Box<dyn State>>, config: TransitionConfig, direction: TransitionDirection) -> Self {
         TransitionState {
-            next_state: Some(next_state),
-            transition: Transition::new(Duration::from_secs(2), state),
-            prev_cells: vec![],
+            next_state,
+            transition: Transition::new(config, Some(direction)),
+            snapshot: None,
+            timestep: FixedTimestep::default(),
         }
     }
+
+    fn is_revealing(&self) -> bool {
+        matches!(self.transition.state(), Some(TransitionDirection::Out))
+    }
+
+    fn finish_covering(&mut self) -> Option<StateChange> {
+        // The screen is fully covered, so the next state can take our place underneath
+        if let Some(next_state) = self.next_state.take() {
+            let mut reveal = TransitionState::reveal(*self.transition.config());
+            reveal.snapshot = self.snapshot.take();
+            return Some(StateChange::Batch(vec![
+                StateChange::Replace(next_state),
+                StateChange::Push(Box::new(reveal)),
+            ]));
+        }
+
+        self.transition.change_state(TransitionDirection::Out);
+        None
+    }
 }
 
 impl State for TransitionState {
-    fn enter(&mut self, screen: &mut Screen, _input: &mut Input) {
-        self.transition.resize(screen.width(), screen.height());
-        if self.transition.state().is_none() {
-            self.transition.change_state(TransitionDirection::In);
+    fn enter(&mut self, screen: &mut Screen, _input: &mut Input, ctx: &mut Context) {
+        // --transition swaps in one effect everywhere, keeping each transition's timing
+        if let Some(effect) = ctx.transition {
+            let config = *self.transition.config();
+            let config = TransitionConfig::new(effect).duration(config.duration).easing(config.easing);
+            self.transition = Transition::new(config, self.transition.state());
         }
-        self.prev_cells = screen.clone_buffer();
-        
-        if let Some(next_state) = self.next_state.as_mut() {
-            next_state.enter(screen, _input);
+
+        self.transition.resize(screen.width(), screen.height());
+        if self.snapshot.is_none() {
+            self.snapshot = Some(screen.snapshot(LayerId::Transition));
         }
     }
 
-    fn update(&mut self, screen: &mut Screen, _input: &mut Input, dt: f64) -> Option<Box<dyn State>> {
+    fn update(&mut self, screen: &mut Screen, _input: &mut Input, _ctx: &mut Context, dt: f64) -> Option<StateChange> {
         
-        if let Some((width, height)) = _input.resized() {
-            self.transition.resize(width, height);
+        if _input.resized().is_some() {
+            self.transition.resize(screen.width(), screen.height());
         }
-        
+
+        let mut done = false;
+        for _ in 0..self.timestep.advance(dt) {
+            if self.transition.update(self.timestep.step()) {
+                done = true;
+                break;
+            }
+        }
+
+        screen.set_layer(LayerId::Transition);
+        let is_crossfade = self.transition.config().effect == TransitionEffect::Crossfade;
+
+        // A crossfade blends the old screen away over the states below instead of covering it
+        let opacity = if is_crossfade && self.is_revealing() { 1.0 - self.transition.progress() } else { 1.0 };
+        screen.layer_mut(LayerId::Transition).opacity = opacity;
+
         match self.transition.state() {
             Some(TransitionDirection::In) => {
-                let done = self.transition.update(screen, dt);
-
-                for (i, cell) in self.prev_cells.iter().enumerate() {
-                    let pos = screen.index_to_xy(i);
-                    screen.set_cell(pos.0, pos.1, cell.rune, cell.color);
+                if let Some(snapshot) = &self.snapshot {
+                    screen.draw_canvas(snapshot, 0, 0);
                 }
 
-                if done {
-                    self.transition.change_state(TransitionDirection::Out);
+                // A crossfade has nothing to cover with, it goes straight to blending into the next state
+                if done || is_crossfade {
+                    self.transition.draw(screen);
+                    return self.finish_covering();
                 }
             }
-            Some(TransitionDirection::Out) => {
-                let done = self.transition.update(screen, dt);
-                let next_state = self.next_state.as_mut().unwrap();
-
-                if let Some(next_state) = next_state.update(screen, _input, dt) {
-                    return Some(next_state);
-                }
-
-                if done {
-                    return self.next_state.take();
+            Some(TransitionDirection::Out) if done => {
+                return Some(StateChange::Pop);
+            }
+            Some(TransitionDirection::Out) if is_crossfade => {
+                if let Some(snapshot) = &self.snapshot {
+                    screen.draw_canvas(snapshot, 0, 0);
                 }
             }
+            Some(TransitionDirection::Out) => {}
             None => {}
         }
 
@@ -71,6 +127,15 @@ impl State for TransitionState {
         None
     }
 
-    fn exit(&mut self, _screen: &mut Screen, _input: &mut Input) {
+    fn exit(&mut self, _screen: &mut Screen, _input: &mut Input, _ctx: &mut Context) {
+    }
+
+    // While revealing, the state below draws and runs underneath the wipe
+    fn is_transparent(&self) -> bool {
+        self.is_revealing()
+    }
+
+    fn updates_below(&self) -> bool {
+        self.is_revealing()
     }
-}
\ No newline at end of file
+}
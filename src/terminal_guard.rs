@@ -0,0 +1,84 @@
+use std::backtrace::Backtrace;
+use std::fs;
+use std::io::{stdout, Error, Write};
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use crossterm::{cursor, queue, terminal};
+use crossterm::event::{DisableFocusChange, DisableMouseCapture, PopKeyboardEnhancementFlags};
+use crossterm::style::{Attribute, SetAttribute};
+use crossterm::terminal::disable_raw_mode;
+
+// Global so the panic hook can restore the terminal without access to the Screen
+static TERMINAL_ACTIVE: AtomicBool = AtomicBool::new(false);
+static KEYBOARD_ENHANCED: AtomicBool = AtomicBool::new(false);
+
+// Restores the terminal when dropped, so early returns and unwinding can't leave it in raw mode
+pub struct TerminalGuard {
+    _private: (),
+}
+
+impl TerminalGuard {
+    pub fn new() -> TerminalGuard {
+        TERMINAL_ACTIVE.store(true, Ordering::SeqCst);
+        TerminalGuard { _private: () }
+    }
+
+    pub fn set_keyboard_enhanced(&self, enhanced: bool) {
+        KEYBOARD_ENHANCED.store(enhanced, Ordering::SeqCst);
+    }
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        let _ = restore_terminal();
+    }
+}
+
+// Undoes everything the terminal backend set up. Safe to call more than once.
+pub fn restore_terminal() -> Result<(), Error> {
+    if !TERMINAL_ACTIVE.swap(false, Ordering::SeqCst) {
+        return Ok(());
+    }
+
+    let mut stdout = stdout();
+
+    // Keep going on errors, a half restored terminal is better than none
+    let raw_mode = disable_raw_mode();
+
+    if KEYBOARD_ENHANCED.swap(false, Ordering::SeqCst) {
+        let _ = queue!(stdout, PopKeyboardEnhancementFlags);
+    }
+
+    queue!(
+        stdout,
+        SetAttribute(Attribute::Reset),
+        cursor::Show,
+        terminal::LeaveAlternateScreen,
+        DisableMouseCapture,
+        DisableFocusChange
+    )?;
+
+    stdout.flush()?;
+    raw_mode
+}
+
+pub fn install_panic_hook() {
+    std::panic::set_hook(Box::new(|info| {
+        let _ = restore_terminal();
+
+        let backtrace = Backtrace::force_capture();
+        let report = format!("{}\n\nBacktrace:\n{}", info, backtrace);
+
+        eprintln!("{}", report);
+
+        let path = crash_log_path();
+        match fs::write(&path, &report) {
+            Ok(()) => eprintln!("\nCrash log written to {}", path.display()),
+            Err(error) => eprintln!("\nCould not write crash log to {}: {}", path.display(), error),
+        }
+    }));
+}
+
+fn crash_log_path() -> PathBuf {
+    std::env::temp_dir().join("my_project-crash.log")
+}
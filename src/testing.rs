@@ -0,0 +1,120 @@
+use std::cell::RefCell;
+use std::io::Error;
+use std::rc::Rc;
+use chrono::NaiveDate;
+use crossterm::event::{Event, KeyCode, MouseButton};
+use crate::backend::MemoryBackend;
+use crate::context::Context;
+use crate::event_source::{key_press, key_release, mouse_down, mouse_move, mouse_up, EventSource, ScriptedEvents};
+use crate::input::Input;
+use crate::progress::Progress;
+use crate::screen::Screen;
+use crate::state_machine::{State, StateMachine};
+use crate::timestep::SIMULATION_STEP;
+
+pub const SIZE: (u16, u16) = (120, 50);
+
+// Lets a test keep adding frames to a script the Input already owns
+#[derive(Clone)]
+struct SharedScript(Rc<RefCell<ScriptedEvents>>);
+
+impl EventSource for SharedScript {
+    fn begin_frame(&mut self) -> Result<f64, Error> {
+        self.0.borrow_mut().begin_frame()
+    }
+
+    fn poll_event(&mut self) -> Result<Option<Event>, Error> {
+        self.0.borrow_mut().poll_event()
+    }
+}
+
+// Runs states the way main does, against a memory backend and scripted input
+pub struct Harness {
+    pub screen: Screen,
+    pub input: Input,
+    pub ctx: Context,
+    pub state_machine: StateMachine,
+    pub memory: MemoryBackend,
+    script: SharedScript,
+}
+
+impl Harness {
+    pub fn new(today: NaiveDate) -> Harness {
+        let memory = MemoryBackend::new();
+        let screen = Screen::new(Box::new(memory.clone()), SIZE);
+
+        let script = SharedScript(Rc::new(RefCell::new(ScriptedEvents::new())));
+        let mut input = Input::with_source(Box::new(script.clone()));
+        input.set_keyboard_mode(screen.keyboard_mode());
+
+        Harness {
+            screen,
+            input,
+            ctx: Context::new(1, today, false, Progress::in_memory()),
+            state_machine: StateMachine::new(),
+            memory,
+            script,
+        }
+    }
+
+    pub fn with_state(today: NaiveDate, state: Box<dyn State>) -> Harness {
+        let mut harness = Harness::new(today);
+        harness.push(state);
+        harness
+    }
+
+    pub fn push(&mut self, state: Box<dyn State>) {
+        self.state_machine.push(&mut self.screen, &mut self.input, &mut self.ctx, state);
+    }
+
+    // One frame of the main loop, with these events arriving during it
+    pub fn frame(&mut self, events: Vec<Event>) {
+        self.script.0.borrow_mut().push_frame(SIMULATION_STEP, events);
+        self.input.update().unwrap();
+
+        if let Some(size) = self.input.resized() {
+            self.screen.resize(size);
+            self.input.set_origin(self.screen.origin());
+        }
+
+        self.screen.clear();
+        let dt = self.input.delta_time();
+        self.state_machine.update(&mut self.screen, &mut self.input, &mut self.ctx, dt);
+        self.screen.render();
+    }
+
+    pub fn idle(&mut self, frames: usize) {
+        for _ in 0..frames {
+            self.frame(vec![]);
+        }
+    }
+
+    // Pressed in one frame and let go in the next
+    pub fn key(&mut self, code: KeyCode) {
+        self.frame(vec![key_press(code)]);
+        self.frame(vec![key_release(code)]);
+    }
+
+    // Moves there and presses the left button in one frame, lets go in the next
+    pub fn click(&mut self, (x, y): (u16, u16)) {
+        self.frame(vec![mouse_move(x, y), mouse_down(MouseButton::Left, x, y)]);
+        self.frame(vec![mouse_up(MouseButton::Left, x, y)]);
+    }
+
+    pub fn text(&self) -> String {
+        self.memory.text()
+    }
+
+    // Where this text starts in the last frame, searching row by row
+    pub fn find(&self, text: &str) -> Option<(u16, u16)> {
+        self.text().lines().enumerate().find_map(|(y, line)| {
+            let byte = line.find(text)?;
+            let x = crate::drawing::text_width(&line[..byte]);
+            Some((x as u16, y as u16))
+        })
+    }
+}
+
+pub fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(year, month, day).unwrap()
+}
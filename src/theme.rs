@@ -0,0 +1,58 @@
+use crossterm::style::Color;
+
+// Colors shared by everything that draws, picked with --theme
+#[derive(Clone, Copy)]
+pub struct Theme {
+    pub name: &'static str,
+    pub text: Color,
+    pub background: Color,
+    pub hover: Color,
+    pub pressed: Color,
+    pub locked: Color,
+    pub done: Color,
+    pub star: Color,
+    pub snow: Color,
+}
+
+pub const CLASSIC: Theme = Theme {
+    name: "classic",
+    text: Color::White,
+    background: Color::Reset,
+    hover: Color::Rgb { r: 255, g: 255, b: 0 },
+    pressed: Color::Rgb { r: 0, g: 255, b: 0 },
+    locked: Color::Rgb { r: 90, g: 110, b: 160 },
+    done: Color::Rgb { r: 120, g: 220, b: 120 },
+    star: Color::Yellow,
+    snow: Color::White,
+};
+
+pub const FROST: Theme = Theme {
+    name: "frost",
+    text: Color::Rgb { r: 200, g: 230, b: 255 },
+    background: Color::Rgb { r: 10, g: 20, b: 45 },
+    hover: Color::Rgb { r: 120, g: 220, b: 255 },
+    pressed: Color::Rgb { r: 255, g: 255, b: 255 },
+    locked: Color::Rgb { r: 70, g: 90, b: 130 },
+    done: Color::Rgb { r: 150, g: 255, b: 200 },
+    star: Color::Rgb { r: 255, g: 240, b: 150 },
+    snow: Color::Rgb { r: 230, g: 245, b: 255 },
+};
+
+// Only the basic colors, for terminals without true color
+pub const MONO: Theme = Theme {
+    name: "mono",
+    text: Color::White,
+    background: Color::Reset,
+    hover: Color::Grey,
+    pressed: Color::White,
+    locked: Color::DarkGrey,
+    done: Color::White,
+    star: Color::White,
+    snow: Color::Grey,
+};
+
+pub const THEMES: &[Theme] = &[CLASSIC, FROST, MONO];
+
+pub fn find(name: &str) -> Option<Theme> {
+    THEMES.iter().find(|theme| theme.name == name).copied()
+}
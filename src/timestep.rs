@@ -0,0 +1,74 @@
+// Simulations advance in steps of this size no matter how fast frames are drawn
+pub const SIMULATION_STEP: f64 = 1.0 / 60.0;
+
+// After a long stall, like a suspended terminal, skip ahead instead of catching up
+const MAX_STEPS_PER_FRAME: usize = 8;
+
+pub struct FixedTimestep {
+    step: f64,
+    accumulator: f64,
+}
+
+impl FixedTimestep {
+    pub fn new(step: f64) -> FixedTimestep {
+        FixedTimestep {
+            step,
+            accumulator: 0.0,
+        }
+    }
+
+    pub fn step(&self) -> f64 {
+        self.step
+    }
+
+    // Adds a frame's worth of time and returns how many steps to simulate
+    pub fn advance(&mut self, dt: f64) -> usize {
+        // Time past the cap is dropped on purpose, the simulation jumps ahead instead of
+        // running fast for the next frames to catch up
+        let max = MAX_STEPS_PER_FRAME as f64 * self.step;
+        self.accumulator = (self.accumulator + dt).min(max);
+        let steps = ((self.accumulator / self.step).floor() as usize).min(MAX_STEPS_PER_FRAME);
+        self.accumulator -= steps as f64 * self.step;
+        steps
+    }
+
+    // How far the frame is between the last step and the next, for interpolating when drawing
+    pub fn alpha(&self) -> f64 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        FixedTimestep::new(SIMULATION_STEP)
+    }
+}
+
+pub fn lerp(from: f64, to: f64, alpha: f64) -> f64 {
+    from + (to - from) * alpha
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{FixedTimestep, MAX_STEPS_PER_FRAME};
+
+    #[test]
+    fn steps_add_up_across_frames() {
+        let mut timestep = FixedTimestep::new(0.1);
+        assert_eq!(timestep.advance(0.05), 0);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-9);
+        assert_eq!(timestep.advance(0.2), 2);
+        assert!((timestep.alpha() - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn a_long_frame_runs_at_most_the_cap_and_drops_the_rest() {
+        let mut timestep = FixedTimestep::new(0.125);
+        assert_eq!(timestep.advance(10.0), MAX_STEPS_PER_FRAME);
+        assert!(timestep.alpha() < 1e-9);
+
+        // Nothing left over to catch up on
+        assert_eq!(timestep.advance(0.125), 1);
+        assert_eq!(timestep.advance(0.0), 0);
+    }
+}
@@ -1,37 +1,152 @@
 use std::time::Duration;
 use crossterm::style;
+use serde::{Deserialize, Serialize};
+use crate::easing::Easing;
 use crate::screen::Screen;
 
 struct Cell {
-    x: u16,
-    y: u16,
+    x: i32,
+    y: i32,
+    // When during the transition, from 0 to 1, this cell gets covered
+    threshold: f64,
     alive: bool,
 }
+
 pub enum TransitionDirection {
     In,
     Out,
 }
 
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TransitionEffect {
+    // Grows from the middle of the screen
+    Radial,
+    WipeLeft,
+    WipeRight,
+    WipeUp,
+    WipeDown,
+    // Cells flip in a random looking order
+    Dissolve,
+    // Falls from the top with a ragged edge
+    SnowCurtain,
+    // Blends the previous frame into the new one, without covering the screen in between
+    Crossfade,
+}
+
+impl TransitionEffect {
+    pub fn find(name: &str) -> Option<TransitionEffect> {
+        match name {
+            "radial" => Some(TransitionEffect::Radial),
+            "wipe-left" => Some(TransitionEffect::WipeLeft),
+            "wipe-right" => Some(TransitionEffect::WipeRight),
+            "wipe-up" => Some(TransitionEffect::WipeUp),
+            "wipe-down" => Some(TransitionEffect::WipeDown),
+            "dissolve" => Some(TransitionEffect::Dissolve),
+            "snow" => Some(TransitionEffect::SnowCurtain),
+            "crossfade" => Some(TransitionEffect::Crossfade),
+            _ => None,
+        }
+    }
+}
+
+pub const BRICKS: &[&str] = &[
+    " _|_    ",
+    "  |     ",
+    "    _|_ ",
+    "     |  ",
+];
+
+pub const SNOW: &[&str] = &[
+    "*  ·  • ",
+    " ·  *  ·",
+    "•  ·  * ",
+    " *  •  ·",
+];
+
+#[derive(Clone, Copy)]
+pub struct TransitionConfig {
+    pub effect: TransitionEffect,
+    pub duration: Duration,
+    pub easing: Easing,
+    // Rows repeated across the covered cells
+    pub pattern: &'static [&'static str],
+    // Defaults to the theme's text color
+    pub color: Option<style::Color>,
+}
+
+impl TransitionConfig {
+    pub fn new(effect: TransitionEffect) -> TransitionConfig {
+        let pattern = match effect {
+            TransitionEffect::SnowCurtain => SNOW,
+            _ => BRICKS,
+        };
+
+        TransitionConfig {
+            effect,
+            duration: Duration::from_secs(2),
+            easing: Easing::Linear,
+            pattern,
+            color: None,
+        }
+    }
+
+    pub fn duration(mut self, duration: Duration) -> TransitionConfig {
+        self.duration = duration;
+        self
+    }
+
+    pub fn easing(mut self, easing: Easing) -> TransitionConfig {
+        self.easing = easing;
+        self
+    }
+
+    pub fn pattern(mut self, pattern: &'static [&'static str]) -> TransitionConfig {
+        self.pattern = pattern;
+        self
+    }
+
+    pub fn color(mut self, color: style::Color) -> TransitionConfig {
+        self.color = Some(color);
+        self
+    }
+}
+
+impl Default for TransitionConfig {
+    fn default() -> Self {
+        TransitionConfig::new(TransitionEffect::Radial)
+    }
+}
+
 pub struct Transition {
     cells: Vec<Cell>,
-    duration: Duration,
+    config: TransitionConfig,
     timer: f64,
     state: Option<TransitionDirection>,
 }
 
 impl Transition {
-    pub fn new(duration: Duration, state: Option<TransitionDirection>) -> Transition {
+    pub fn new(config: TransitionConfig, state: Option<TransitionDirection>) -> Transition {
         Transition {
             cells: Vec::new(),
-            duration,
+            config,
             timer: 0.0,
             state,
         }
     }
 
-    pub fn resize(&mut self, width: u16, height: u16) {
+    pub fn config(&self) -> &TransitionConfig {
+        &self.config
+    }
+
+    pub fn resize(&mut self, width: i32, height: i32) {
+        let effect = self.config.effect;
         self.cells = (0..width)
-            .flat_map(|x| (0..height).map(move |y| Cell { x, y, alive: false }))
+            .flat_map(|x| (0..height).map(move |y| Cell {
+                x,
+                y,
+                threshold: threshold(effect, x, y, width, height),
+                alive: false,
+            }))
             .collect();
     }
 
@@ -56,44 +171,136 @@ impl Transition {
         }
     }
 
-    pub fn update(&mut self, screen: &mut Screen, dt: f64) -> bool {
-        self.timer += dt;
+    // Eased progress through the current direction, from 0 to 1
+    pub fn progress(&self) -> f64 {
+        let duration = self.config.duration.as_secs_f64().max(f64::EPSILON);
+        self.config.easing.apply(self.timer / duration)
+    }
 
-        let normalized_timer = self.timer / self.duration.as_secs_f64();
-        let center_x = screen.width() as f64 / 2.0;
-        let center_y = screen.height() as f64 / 2.0;
+    pub fn update(&mut self, dt: f64) -> bool {
+        self.timer += dt;
 
+        let progress = self.progress();
         for cell in &mut self.cells {
-            let dx = (cell.x as f64 - center_x) / center_x;
-            let dy = (cell.y as f64 - center_y) / center_y;
-            let distance = (dx * dx + dy * dy).sqrt();
-
-            let distance = distance / 1.5;
-
             cell.alive = match self.state {
-                Some(TransitionDirection::In) => distance < normalized_timer,
-                Some(TransitionDirection::Out) => distance > normalized_timer,
+                Some(TransitionDirection::In) => cell.threshold < progress,
+                Some(TransitionDirection::Out) => cell.threshold >= progress,
                 None => false,
             };
         }
 
-        self.timer >= self.duration.as_secs_f64()
+        self.timer >= self.config.duration.as_secs_f64()
     }
 
+    // Covers the screen with the pattern, crossfades have nothing to draw and blend a whole layer instead
     pub fn draw(&self, screen: &mut Screen) {
-        const PATTERN: [[char; 8]; 4] = [
-            [' ', '_', '|', '_', ' ', ' ', ' ', ' '],
-            [' ', ' ', '|', ' ', ' ', ' ', ' ', ' '],
-            [' ', ' ', ' ', ' ', '_', '|', '_', ' '],
-            [' ', ' ', ' ', ' ', ' ', '|', ' ', ' ']
-        ];
+        if self.config.effect == TransitionEffect::Crossfade {
+            return;
+        }
 
+        let color = self.config.color.unwrap_or(screen.theme().text);
         for cell in &self.cells {
             if cell.alive {
-                let index = (cell.y % PATTERN.len() as u16) as usize;
-                let rune = PATTERN[index][cell.x as usize % PATTERN[index].len()];
-                screen.set_cell(cell.x, cell.y, rune, style::Color::White);
+                let row = self.config.pattern[cell.y as usize % self.config.pattern.len()];
+                let length = row.chars().count().max(1);
+                let rune = row.chars().nth(cell.x as usize % length).unwrap_or(' ');
+                screen.set_cell(cell.x, cell.y, rune, color);
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn threshold(effect: TransitionEffect, x: i32, y: i32, width: i32, height: i32) -> f64 {
+    let width = width.max(1) as f64;
+    let height = height.max(1) as f64;
+    let (fx, fy) = (x as f64, y as f64);
+
+    match effect {
+        TransitionEffect::Radial => {
+            let center_x = width / 2.0;
+            let center_y = height / 2.0;
+            let dx = (fx - center_x) / center_x;
+            let dy = (fy - center_y) / center_y;
+            (dx * dx + dy * dy).sqrt() / 1.5
+        }
+        TransitionEffect::WipeRight => fx / width,
+        TransitionEffect::WipeLeft => 1.0 - (fx + 1.0) / width,
+        TransitionEffect::WipeDown => fy / height,
+        TransitionEffect::WipeUp => 1.0 - (fy + 1.0) / height,
+        TransitionEffect::Dissolve | TransitionEffect::Crossfade => noise(x as u32, y as u32),
+        TransitionEffect::SnowCurtain => fy / height * 0.7 + noise(x as u32, 0) * 0.3,
+    }
+}
+
+// Cheap hash of a cell position to a number from 0 to 1, the same every time
+pub fn noise(x: u32, y: u32) -> f64 {
+    let mut hash = x.wrapping_mul(374_761_393) ^ y.wrapping_mul(668_265_263);
+    hash = (hash ^ (hash >> 13)).wrapping_mul(1_274_126_177);
+    hash ^= hash >> 16;
+    (hash % 10_000) as f64 / 10_000.0
+}
+
+fn to_rgb(color: style::Color) -> (u8, u8, u8) {
+    match color {
+        style::Color::Rgb { r, g, b } => (r, g, b),
+        style::Color::Black | style::Color::Reset => (0, 0, 0),
+        style::Color::DarkGrey => (128, 128, 128),
+        style::Color::Red => (255, 0, 0),
+        style::Color::DarkRed => (128, 0, 0),
+        style::Color::Green => (0, 255, 0),
+        style::Color::DarkGreen => (0, 128, 0),
+        style::Color::Yellow => (255, 255, 0),
+        style::Color::DarkYellow => (128, 128, 0),
+        style::Color::Blue => (0, 0, 255),
+        style::Color::DarkBlue => (0, 0, 128),
+        style::Color::Magenta => (255, 0, 255),
+        style::Color::DarkMagenta => (128, 0, 128),
+        style::Color::Cyan => (0, 255, 255),
+        style::Color::DarkCyan => (0, 128, 128),
+        style::Color::White => (255, 255, 255),
+        style::Color::Grey => (192, 192, 192),
+        style::Color::AnsiValue(_) => (192, 192, 192),
+    }
+}
+
+pub fn blend_colors(from: style::Color, to: style::Color, t: f64) -> style::Color {
+    if from == to {
+        return to;
+    }
+
+    let (from, to) = (to_rgb(from), to_rgb(to));
+    let mix = |a: u8, b: u8| (a as f64 + (b as f64 - a as f64) * t.clamp(0.0, 1.0)).round() as u8;
+    style::Color::Rgb {
+        r: mix(from.0, to.0),
+        g: mix(from.1, to.1),
+        b: mix(from.2, to.2),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{threshold, TransitionEffect};
+
+    #[test]
+    fn wipes_start_from_their_edge() {
+        let covered_first = |effect| {
+            let cells = [(0, 5), (9, 5), (5, 0), (5, 9)];
+            let first = cells.iter().min_by(|a, b| {
+                threshold(effect, a.0, a.1, 10, 10).total_cmp(&threshold(effect, b.0, b.1, 10, 10))
+            });
+            *first.unwrap()
+        };
+
+        assert_eq!(covered_first(TransitionEffect::WipeRight), (0, 5));
+        assert_eq!(covered_first(TransitionEffect::WipeLeft), (9, 5));
+        assert_eq!(covered_first(TransitionEffect::WipeDown), (5, 0));
+        assert_eq!(covered_first(TransitionEffect::WipeUp), (5, 9));
+    }
+
+    #[test]
+    fn effects_are_found_by_name() {
+        assert!(TransitionEffect::find("wipe-left") == Some(TransitionEffect::WipeLeft));
+        assert!(TransitionEffect::find("snow") == Some(TransitionEffect::SnowCurtain));
+        assert!(TransitionEffect::find("sideways").is_none());
+    }
+}
@@ -0,0 +1,91 @@
+use crossterm::style;
+use crossterm::style::Attributes;
+use crate::drawing::{draw_text, text_width};
+use crate::rect::Rect;
+use crate::screen::Screen;
+use crate::widgets::widget::{draw_border, Interaction, Style, Widget};
+
+// Text in a rounded box that can be clicked, or focused and activated with the keyboard
+pub struct Button {
+    id: usize,
+    text: String,
+    style: Style,
+    // Drawn in the top border near the right corner, like a star on a finished door
+    mark: Option<(char, style::Color)>,
+    area: Rect,
+}
+
+impl Button {
+    pub fn new(id: usize, text: impl Into<String>) -> Button {
+        Button {
+            id,
+            text: text.into(),
+            style: Style::default(),
+            mark: None,
+            area: Rect::default(),
+        }
+    }
+
+    // Border and text color while the button is neither hovered nor focused
+    pub fn color(mut self, color: style::Color) -> Button {
+        self.style.color = Some(color);
+        self
+    }
+
+    // Behind the text only, the border keeps the theme's background
+    pub fn background(mut self, color: style::Color) -> Button {
+        self.style.background = Some(color);
+        self
+    }
+
+    pub fn attributes(mut self, attributes: Attributes) -> Button {
+        self.style.attributes = attributes;
+        self
+    }
+
+    pub fn mark(mut self, c: char, color: style::Color) -> Button {
+        self.mark = Some((c, color));
+        self
+    }
+}
+
+impl Widget for Button {
+    // Two columns of padding on either side of the text, inside the border
+    fn measure(&self) -> (i32, i32) {
+        (text_width(&self.text) + 6, 3)
+    }
+
+    fn layout(&mut self, area: Rect) {
+        self.area = area;
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn draw(&self, screen: &mut Screen, interaction: &Interaction) {
+        let area = self.area;
+        let color = interaction.color(Some(self.id), screen, self.style.color(screen));
+        draw_border(screen, area, color);
+
+        if let Some(background) = self.style.background {
+            for x in area.x + 1..area.right() - 1 {
+                screen.set_background(x, area.y + 1, background);
+            }
+        }
+
+        let x = area.x + (area.width - text_width(&self.text)) / 2;
+        let width = draw_text(screen, x, area.y + 1, &self.text, color);
+        for i in 0..width {
+            screen.set_attributes(x + i, area.y + 1, self.style.attributes);
+        }
+
+        if let Some((c, mark_color)) = self.mark {
+            screen.set_cell(area.right() - 2, area.y, c, mark_color);
+        }
+    }
+
+    fn id(&self) -> Option<usize> {
+        Some(self.id)
+    }
+}
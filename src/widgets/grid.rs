@@ -0,0 +1,113 @@
+use crate::rect::Rect;
+use crate::screen::Screen;
+use crate::widgets::widget::{Interaction, Widget};
+
+// Rows of equally sized cells, filled left to right and each child centered in its own
+pub struct Grid {
+    columns: usize,
+    gap: (i32, i32),
+    children: Vec<Box<dyn Widget>>,
+    area: Rect,
+}
+
+impl Grid {
+    pub fn new(columns: usize) -> Grid {
+        Grid {
+            columns: columns.max(1),
+            gap: (0, 0),
+            children: Vec::new(),
+            area: Rect::default(),
+        }
+    }
+
+    // Empty columns and rows between neighbouring cells
+    pub fn gap(mut self, x: i32, y: i32) -> Grid {
+        self.gap = (x, y);
+        self
+    }
+
+    pub fn child(mut self, child: impl Widget + 'static) -> Grid {
+        self.children.push(Box::new(child));
+        self
+    }
+
+    fn cell_size(&self) -> (i32, i32) {
+        self.children
+            .iter()
+            .map(|child| child.measure())
+            .fold((0, 0), |(width, height), size| (width.max(size.0), height.max(size.1)))
+    }
+
+    fn rows(&self) -> usize {
+        self.children.len().div_ceil(self.columns)
+    }
+}
+
+impl Widget for Grid {
+    fn measure(&self) -> (i32, i32) {
+        let (cell_width, cell_height) = self.cell_size();
+        let columns = self.columns.min(self.children.len()) as i32;
+        let rows = self.rows() as i32;
+        (
+            (cell_width * columns + self.gap.0 * (columns - 1)).max(0),
+            (cell_height * rows + self.gap.1 * (rows - 1)).max(0),
+        )
+    }
+
+    fn layout(&mut self, area: Rect) {
+        self.area = area;
+        let (cell_width, cell_height) = self.cell_size();
+
+        for (i, child) in self.children.iter_mut().enumerate() {
+            let column = (i % self.columns) as i32;
+            let row = (i / self.columns) as i32;
+            let (width, height) = child.measure();
+            child.layout(Rect::new(
+                area.x + column * (cell_width + self.gap.0) + (cell_width - width) / 2,
+                area.y + row * (cell_height + self.gap.1) + (cell_height - height) / 2,
+                width,
+                height,
+            ));
+        }
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn draw(&self, screen: &mut Screen, interaction: &Interaction) {
+        for child in &self.children {
+            child.draw(screen, interaction);
+        }
+    }
+
+    fn children(&self) -> &[Box<dyn Widget>] {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        &mut self.children
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rect::Rect;
+    use crate::widgets::label::Label;
+    use crate::widgets::widget::Widget;
+    use super::Grid;
+
+    #[test]
+    fn cells_are_as_big_as_the_biggest_child() {
+        let mut grid = Grid::new(2).gap(1, 1).child(Label::new("a")).child(Label::new("bbb")).child(Label::new("c"));
+        assert_eq!(grid.measure(), (7, 3));
+
+        grid.layout(Rect::new(10, 20, 7, 3));
+        let areas: Vec<Rect> = grid.children().iter().map(|child| child.area()).collect();
+        assert_eq!(areas, vec![
+            Rect::new(11, 20, 1, 1),
+            Rect::new(14, 20, 3, 1),
+            Rect::new(11, 22, 1, 1),
+        ]);
+    }
+}
@@ -0,0 +1,54 @@
+use crossterm::style;
+use crossterm::style::Attributes;
+use crate::drawing::{draw_text, text_width};
+use crate::rect::Rect;
+use crate::screen::Screen;
+use crate::widgets::widget::{Interaction, Style, Widget};
+
+// A single line of text that can't be interacted with
+pub struct Label {
+    text: String,
+    style: Style,
+    area: Rect,
+}
+
+impl Label {
+    pub fn new(text: impl Into<String>) -> Label {
+        Label {
+            text: text.into(),
+            style: Style::default(),
+            area: Rect::default(),
+        }
+    }
+
+    pub fn color(mut self, color: style::Color) -> Label {
+        self.style.color = Some(color);
+        self
+    }
+
+    pub fn attributes(mut self, attributes: Attributes) -> Label {
+        self.style.attributes = attributes;
+        self
+    }
+}
+
+impl Widget for Label {
+    fn measure(&self) -> (i32, i32) {
+        (text_width(&self.text), 1)
+    }
+
+    fn layout(&mut self, area: Rect) {
+        self.area = area;
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn draw(&self, screen: &mut Screen, _interaction: &Interaction) {
+        let width = draw_text(screen, self.area.x, self.area.y, &self.text, self.style.color(screen));
+        for x in self.area.x..self.area.x + width {
+            screen.set_attributes(x, self.area.y, self.style.attributes);
+        }
+    }
+}
@@ -0,0 +1,179 @@
+use crate::rect::Rect;
+use crate::screen::Screen;
+use crate::widgets::widget::{Interaction, Widget};
+
+#[derive(Clone, Copy, PartialEq)]
+enum Direction {
+    Horizontal,
+    Vertical,
+}
+
+// Stacks children from the top down, each centered across
+#[derive(Default)]
+pub struct VBox {
+    spacing: i32,
+    children: Vec<Box<dyn Widget>>,
+    area: Rect,
+}
+
+// Lines children up from left to right, each centered from top to bottom
+#[derive(Default)]
+pub struct HBox {
+    spacing: i32,
+    children: Vec<Box<dyn Widget>>,
+    area: Rect,
+}
+
+impl VBox {
+    pub fn new() -> VBox {
+        VBox::default()
+    }
+
+    // Empty rows between neighbouring children
+    pub fn spacing(mut self, spacing: i32) -> VBox {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn child(mut self, child: impl Widget + 'static) -> VBox {
+        self.children.push(Box::new(child));
+        self
+    }
+}
+
+impl HBox {
+    pub fn new() -> HBox {
+        HBox::default()
+    }
+
+    // Empty columns between neighbouring children
+    pub fn spacing(mut self, spacing: i32) -> HBox {
+        self.spacing = spacing;
+        self
+    }
+
+    pub fn child(mut self, child: impl Widget + 'static) -> HBox {
+        self.children.push(Box::new(child));
+        self
+    }
+}
+
+impl Widget for VBox {
+    fn measure(&self) -> (i32, i32) {
+        measure_line(&self.children, self.spacing, Direction::Vertical)
+    }
+
+    fn layout(&mut self, area: Rect) {
+        self.area = area;
+        layout_line(&mut self.children, self.spacing, Direction::Vertical, area);
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn draw(&self, screen: &mut Screen, interaction: &Interaction) {
+        for child in &self.children {
+            child.draw(screen, interaction);
+        }
+    }
+
+    fn children(&self) -> &[Box<dyn Widget>] {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        &mut self.children
+    }
+}
+
+impl Widget for HBox {
+    fn measure(&self) -> (i32, i32) {
+        measure_line(&self.children, self.spacing, Direction::Horizontal)
+    }
+
+    fn layout(&mut self, area: Rect) {
+        self.area = area;
+        layout_line(&mut self.children, self.spacing, Direction::Horizontal, area);
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn draw(&self, screen: &mut Screen, interaction: &Interaction) {
+        for child in &self.children {
+            child.draw(screen, interaction);
+        }
+    }
+
+    fn children(&self) -> &[Box<dyn Widget>] {
+        &self.children
+    }
+
+    fn children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        &mut self.children
+    }
+}
+
+fn measure_line(children: &[Box<dyn Widget>], spacing: i32, direction: Direction) -> (i32, i32) {
+    let gaps = spacing * (children.len() as i32 - 1).max(0);
+    let sizes = children.iter().map(|child| child.measure());
+
+    match direction {
+        Direction::Horizontal => sizes.fold((gaps, 0), |(width, height), size| (width + size.0, height.max(size.1))),
+        Direction::Vertical => sizes.fold((0, gaps), |(width, height), size| (width.max(size.0), height + size.1)),
+    }
+}
+
+fn layout_line(children: &mut [Box<dyn Widget>], spacing: i32, direction: Direction, area: Rect) {
+    let mut position = match direction {
+        Direction::Horizontal => area.x,
+        Direction::Vertical => area.y,
+    };
+
+    for child in children.iter_mut() {
+        let (width, height) = child.measure();
+        match direction {
+            Direction::Horizontal => {
+                child.layout(Rect::new(position, area.y + (area.height - height) / 2, width, height));
+                position += width + spacing;
+            }
+            Direction::Vertical => {
+                child.layout(Rect::new(area.x + (area.width - width) / 2, position, width, height));
+                position += height + spacing;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rect::Rect;
+    use crate::widgets::label::Label;
+    use crate::widgets::widget::Widget;
+    use super::{HBox, VBox};
+
+    fn areas(widget: &dyn Widget) -> Vec<Rect> {
+        widget.children().iter().map(|child| child.area()).collect()
+    }
+
+    #[test]
+    fn a_vbox_stacks_children_centered_across() {
+        let mut column = VBox::new().spacing(1).child(Label::new("a")).child(Label::new("ccc"));
+        assert_eq!(column.measure(), (3, 3));
+
+        column.layout(Rect::new(0, 0, 5, 3));
+        assert_eq!(areas(&column), vec![Rect::new(2, 0, 1, 1), Rect::new(1, 2, 3, 1)]);
+    }
+
+    #[test]
+    fn an_hbox_lines_children_up_centered_from_top_to_bottom() {
+        let tall = VBox::new().child(Label::new("a")).child(Label::new("b")).child(Label::new("c"));
+        let mut row = HBox::new().spacing(2).child(Label::new("xy")).child(tall);
+        assert_eq!(row.measure(), (5, 3));
+
+        row.layout(Rect::new(10, 10, 5, 3));
+        assert_eq!(areas(&row), vec![Rect::new(10, 11, 2, 1), Rect::new(14, 10, 1, 3)]);
+    }
+}
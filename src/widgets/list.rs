@@ -0,0 +1,134 @@
+use crate::drawing::{draw_text, text_width};
+use crate::input::Input;
+use crate::rect::Rect;
+use crate::screen::Screen;
+use crate::widgets::widget::{Interaction, Widget};
+use crossterm::event::KeyCode;
+
+// Lines of text that scroll when there's less room than there are lines
+pub struct List {
+    id: Option<usize>,
+    lines: Vec<String>,
+    // First line shown
+    scroll: usize,
+    area: Rect,
+}
+
+impl List {
+    pub fn new(lines: Vec<String>) -> List {
+        List {
+            id: None,
+            lines,
+            scroll: 0,
+            area: Rect::default(),
+        }
+    }
+
+    // Lets the list take focus, so the arrow keys scroll it
+    pub fn focusable(mut self, id: usize) -> List {
+        self.id = Some(id);
+        self
+    }
+
+    fn max_scroll(&self) -> usize {
+        self.lines.len().saturating_sub(self.area.height.max(0) as usize)
+    }
+}
+
+impl Widget for List {
+    // One column to the right is kept for the arrows showing there's more
+    fn measure(&self) -> (i32, i32) {
+        let width = self.lines.iter().map(|line| text_width(line)).max().unwrap_or(0);
+        (width + 2, self.lines.len() as i32)
+    }
+
+    fn layout(&mut self, area: Rect) {
+        self.area = area;
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn draw(&self, screen: &mut Screen, interaction: &Interaction) {
+        let focused = self.id.is_some() && interaction.focused == self.id;
+        let color = if focused { screen.theme().hover } else { screen.theme().text };
+
+        screen.push_clip(self.area);
+        for (row, line) in self.lines.iter().skip(self.scroll).take(self.area.height.max(0) as usize).enumerate() {
+            draw_text(screen, self.area.x, self.area.y + row as i32, line, screen.theme().text);
+        }
+
+        let right = self.area.right() - 1;
+        if self.scroll > 0 {
+            screen.set_cell(right, self.area.y, '▲', color);
+        }
+        if self.scroll < self.max_scroll() {
+            screen.set_cell(right, self.area.bottom() - 1, '▼', color);
+        }
+        screen.pop_clip();
+    }
+
+    fn id(&self) -> Option<usize> {
+        self.id
+    }
+
+    fn handle_keys(&mut self, input: &Input) -> bool {
+        if input.is_key_typed(KeyCode::Up) {
+            return self.scroll(-1);
+        }
+        if input.is_key_typed(KeyCode::Down) {
+            return self.scroll(1);
+        }
+        false
+    }
+
+    fn scroll(&mut self, delta: i32) -> bool {
+        let scroll = (self.scroll as i32 + delta).clamp(0, self.max_scroll() as i32) as usize;
+        let moved = scroll != self.scroll;
+        self.scroll = scroll;
+        moved
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rect::Rect;
+    use crate::widgets::widget::Widget;
+    use super::List;
+
+    fn list(lines: usize, height: i32) -> List {
+        let mut list = List::new((0..lines).map(|i| i.to_string()).collect());
+        list.layout(Rect::new(0, 0, 10, height));
+        list
+    }
+
+    #[test]
+    fn scrolling_stops_at_the_last_full_page() {
+        let mut list = list(10, 4);
+        assert_eq!(list.max_scroll(), 6);
+
+        assert!(!list.scroll(-1));
+        assert!(list.scroll(100));
+        assert_eq!(list.scroll, 6);
+        assert!(!list.scroll(1));
+        assert!(list.scroll(-2));
+        assert_eq!(list.scroll, 4);
+    }
+
+    #[test]
+    fn a_list_that_fits_never_scrolls() {
+        let mut list = list(3, 4);
+        assert_eq!(list.max_scroll(), 0);
+        assert!(!list.scroll(1));
+    }
+
+    #[test]
+    fn growing_the_area_pulls_the_scroll_back() {
+        let mut list = list(10, 4);
+        list.scroll(6);
+        list.layout(Rect::new(0, 0, 10, 8));
+        assert_eq!(list.scroll, 2);
+    }
+}
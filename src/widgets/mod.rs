@@ -0,0 +1,9 @@
+pub mod widget;
+pub mod ui;
+pub mod label;
+pub mod button;
+pub mod panel;
+pub mod grid;
+pub mod layout;
+pub mod progress_bar;
+pub mod list;
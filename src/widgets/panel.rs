@@ -0,0 +1,83 @@
+use crossterm::style;
+use crate::rect::Rect;
+use crate::screen::Screen;
+use crate::widgets::widget::{draw_border, Interaction, Style, Widget};
+
+// A border around a single child, with some room between them
+pub struct Panel {
+    child: Box<dyn Widget>,
+    padding: (i32, i32),
+    style: Style,
+    area: Rect,
+}
+
+impl Panel {
+    pub fn new(child: impl Widget + 'static) -> Panel {
+        Panel {
+            child: Box::new(child),
+            padding: (0, 0),
+            style: Style::default(),
+            area: Rect::default(),
+        }
+    }
+
+    // Empty columns and rows between the border and the child
+    pub fn padding(mut self, x: i32, y: i32) -> Panel {
+        self.padding = (x, y);
+        self
+    }
+
+    // Of the border, the child has its own
+    pub fn color(mut self, color: style::Color) -> Panel {
+        self.style.color = Some(color);
+        self
+    }
+
+    // Everywhere inside the border
+    pub fn background(mut self, color: style::Color) -> Panel {
+        self.style.background = Some(color);
+        self
+    }
+}
+
+impl Widget for Panel {
+    fn measure(&self) -> (i32, i32) {
+        let (width, height) = self.child.measure();
+        (width + 2 + self.padding.0 * 2, height + 2 + self.padding.1 * 2)
+    }
+
+    fn layout(&mut self, area: Rect) {
+        self.area = area;
+        let (x, y) = (self.padding.0 + 1, self.padding.1 + 1);
+        self.child.layout(Rect::new(area.x + x, area.y + y, (area.width - x * 2).max(0), (area.height - y * 2).max(0)));
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    // The child stays inside the border, even when the panel got less room than it asked for
+    fn draw(&self, screen: &mut Screen, interaction: &Interaction) {
+        draw_border(screen, self.area, self.style.color(screen));
+
+        if let Some(background) = self.style.background {
+            for y in self.area.y + 1..self.area.bottom() - 1 {
+                for x in self.area.x + 1..self.area.right() - 1 {
+                    screen.set_background(x, y, background);
+                }
+            }
+        }
+
+        screen.push_clip(self.child.area());
+        self.child.draw(screen, interaction);
+        screen.pop_clip();
+    }
+
+    fn children(&self) -> &[Box<dyn Widget>] {
+        std::slice::from_ref(&self.child)
+    }
+
+    fn children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        std::slice::from_mut(&mut self.child)
+    }
+}
@@ -0,0 +1,56 @@
+use crate::drawing::{draw_text, text_width};
+use crate::rect::Rect;
+use crate::screen::Screen;
+use crate::widgets::widget::{Interaction, Widget};
+
+// How far along something is, as a filled bar with an optional label after it
+pub struct ProgressBar {
+    // From 0 to 1
+    value: f64,
+    width: i32,
+    label: Option<String>,
+    area: Rect,
+}
+
+impl ProgressBar {
+    pub fn new(value: f64, width: i32) -> ProgressBar {
+        ProgressBar {
+            value: value.clamp(0.0, 1.0),
+            width,
+            label: None,
+            area: Rect::default(),
+        }
+    }
+
+    pub fn label(mut self, label: impl Into<String>) -> ProgressBar {
+        self.label = Some(label.into());
+        self
+    }
+}
+
+impl Widget for ProgressBar {
+    fn measure(&self) -> (i32, i32) {
+        let label_width = self.label.as_deref().map_or(0, |label| text_width(label) + 1);
+        (self.width + label_width, 1)
+    }
+
+    fn layout(&mut self, area: Rect) {
+        self.area = area;
+    }
+
+    fn area(&self) -> Rect {
+        self.area
+    }
+
+    fn draw(&self, screen: &mut Screen, _interaction: &Interaction) {
+        let filled = (self.value * self.width as f64).round() as i32;
+        for i in 0..self.width {
+            let (c, color) = if i < filled { ('█', screen.theme().done) } else { ('░', screen.theme().text) };
+            screen.set_cell(self.area.x + i, self.area.y, c, color);
+        }
+
+        if let Some(label) = &self.label {
+            draw_text(screen, self.area.x + self.width + 1, self.area.y, label, screen.theme().text);
+        }
+    }
+}
@@ -0,0 +1,300 @@
+use crossterm::event::KeyCode;
+use crate::input::{Input, MouseButton};
+use crate::rect::Rect;
+use crate::screen::Screen;
+use crate::widgets::widget::{Interaction, Widget};
+
+// Owns a tree of widgets centered on the screen, and keeps track of which one has focus
+pub struct Ui {
+    root: Box<dyn Widget>,
+    focused: Option<usize>,
+    hovered: Option<usize>,
+    // The widget the left button went down on, a click needs it to come up there too
+    pressed: Option<usize>,
+    mouse_down: bool,
+    // Moves the whole tree away from the middle of the screen, for sliding it in
+    offset: (i32, i32),
+}
+
+impl Ui {
+    pub fn new(root: impl Widget + 'static) -> Ui {
+        Ui {
+            root: Box::new(root),
+            focused: None,
+            hovered: None,
+            pressed: None,
+            mouse_down: false,
+            offset: (0, 0),
+        }
+    }
+
+    // Swaps in a rebuilt tree, focus stays on the same id if it's still there
+    pub fn set_root(&mut self, root: impl Widget + 'static) {
+        self.root = Box::new(root);
+        if self.focused.is_some_and(|id| !self.focusables().iter().any(|(focusable, _)| *focusable == id)) {
+            self.focused = None;
+        }
+    }
+
+    pub fn set_offset(&mut self, x: i32, y: i32) {
+        self.offset = (x, y);
+    }
+
+    pub fn focused(&self) -> Option<usize> {
+        self.focused
+    }
+
+    pub fn hovered(&self) -> Option<usize> {
+        self.hovered
+    }
+
+    pub fn focus(&mut self, id: Option<usize>) {
+        self.focused = id;
+    }
+
+    // Follows the mouse and keyboard, and returns the id of a widget clicked or activated this frame.
+    // Arrows move focus to the nearest widget that way, tab and the mouse wheel go through them in order.
+    pub fn update(&mut self, screen: &Screen, input: &Input) -> Option<usize> {
+        self.place(screen);
+
+        let (mouse_x, mouse_y) = (input.mouse_position().0 as i32, input.mouse_position().1 as i32);
+        self.hovered = widget_at(self.root.as_ref(), mouse_x, mouse_y);
+        if input.is_mouse_pressed(MouseButton::Left) {
+            self.pressed = self.hovered;
+        }
+        // Only looks pressed while the mouse is still over the widget it went down on
+        self.mouse_down = input.is_mouse_down(MouseButton::Left) && self.pressed == self.hovered;
+
+        let (_, scroll) = input.scroll_delta();
+        if scroll != 0 && !scroll_at(self.root.as_mut(), mouse_x, mouse_y, scroll as i32) {
+            self.step_focus(scroll as i32);
+        }
+
+        let used_keys = match self.focused {
+            Some(id) => find_mut(self.root.as_mut(), id).is_some_and(|widget| widget.handle_keys(input)),
+            None => false,
+        };
+
+        if !used_keys {
+            if input.is_key_typed(KeyCode::Tab) {
+                self.step_focus(1);
+            } else if input.is_key_typed(KeyCode::BackTab) {
+                self.step_focus(-1);
+            } else if let Some((dx, dy)) = input.arrow_direction() {
+                self.move_focus(dx as i32, dy as i32);
+            }
+        }
+
+        if input.is_mouse_up(MouseButton::Left) && self.pressed.take().is_some_and(|id| self.hovered == Some(id)) {
+            self.focused = self.hovered;
+            return self.hovered;
+        }
+
+        if input.is_confirm_pressed() {
+            return self.focused;
+        }
+        None
+    }
+
+    pub fn draw(&mut self, screen: &mut Screen) {
+        self.place(screen);
+
+        let interaction = Interaction {
+            focused: self.focused,
+            hovered: self.hovered,
+            mouse_down: self.mouse_down,
+        };
+        self.root.draw(screen, &interaction);
+    }
+
+    // Centers the tree, shrinking it to the screen if it asks for more room than there is
+    fn place(&mut self, screen: &Screen) {
+        let (width, height) = self.root.measure();
+        let (width, height) = (width.min(screen.width()), height.min(screen.height()));
+        let x = (screen.width() - width) / 2 + self.offset.0;
+        let y = (screen.height() - height) / 2 + self.offset.1;
+        self.root.layout(Rect::new(x, y, width, height));
+    }
+
+    fn focusables(&self) -> Vec<(usize, Rect)> {
+        let mut focusables = Vec::new();
+        collect_focusables(self.root.as_ref(), &mut focusables);
+        focusables
+    }
+
+    // Through the widgets in the order they were added, wrapping around at either end
+    fn step_focus(&mut self, step: i32) {
+        let focusables = self.focusables();
+        if focusables.is_empty() {
+            return;
+        }
+
+        let count = focusables.len() as i32;
+        let index = match self.focused.and_then(|id| focusables.iter().position(|(focusable, _)| *focusable == id)) {
+            Some(index) => (index as i32 + step).rem_euclid(count),
+            None if step < 0 => count - 1,
+            None => 0,
+        };
+        self.focused = Some(focusables[index as usize].0);
+    }
+
+    // To the closest widget in that direction, preferring ones straight ahead over ones off to the side
+    fn move_focus(&mut self, dx: i32, dy: i32) {
+        let focusables = self.focusables();
+        let Some(from) = self.focused.and_then(|id| focusables.iter().find(|(focusable, _)| *focusable == id)) else {
+            self.focused = focusables.first().map(|(id, _)| *id);
+            return;
+        };
+
+        // Doubled, so centers of odd sized widgets stay whole numbers
+        let center = |rect: &Rect| (rect.x * 2 + rect.width, rect.y * 2 + rect.height);
+        let (from_x, from_y) = center(&from.1);
+
+        let closest = focusables
+            .iter()
+            .filter_map(|(id, rect)| {
+                let (x, y) = center(rect);
+                let ahead = (x - from_x) * dx + (y - from_y) * dy;
+                let aside = ((x - from_x) * dy - (y - from_y) * dx).abs();
+                (ahead > 0).then_some((ahead + aside * 2, *id))
+            })
+            .min();
+
+        if let Some((_, id)) = closest {
+            self.focused = Some(id);
+        }
+    }
+}
+
+fn collect_focusables(widget: &dyn Widget, focusables: &mut Vec<(usize, Rect)>) {
+    if let Some(id) = widget.id() {
+        focusables.push((id, widget.area()));
+    }
+    for child in widget.children() {
+        collect_focusables(child.as_ref(), focusables);
+    }
+}
+
+// The innermost widget with an id under the point
+fn widget_at(widget: &dyn Widget, x: i32, y: i32) -> Option<usize> {
+    if !widget.area().contains(x, y) {
+        return None;
+    }
+
+    widget.children()
+        .iter()
+        .find_map(|child| widget_at(child.as_ref(), x, y))
+        .or(widget.id())
+}
+
+// Offers the wheel to the widgets under the point, innermost first
+fn scroll_at(widget: &mut dyn Widget, x: i32, y: i32, delta: i32) -> bool {
+    if !widget.area().contains(x, y) {
+        return false;
+    }
+
+    widget.children_mut().iter_mut().any(|child| scroll_at(child.as_mut(), x, y, delta)) || widget.scroll(delta)
+}
+
+fn find_mut(widget: &mut dyn Widget, id: usize) -> Option<&mut dyn Widget> {
+    if widget.id() == Some(id) {
+        return Some(widget);
+    }
+
+    widget.children_mut().iter_mut().find_map(|child| find_mut(child.as_mut(), id))
+}
+
+#[cfg(test)]
+mod tests {
+    use crossterm::event::{Event, MouseButton};
+    use crate::backend::MemoryBackend;
+    use crate::event_source::{mouse_down, mouse_up, ScriptedEvents};
+    use crate::input::Input;
+    use crate::screen::Screen;
+    use crate::widgets::button::Button;
+    use crate::widgets::grid::Grid;
+    use crate::widgets::layout::HBox;
+    use super::Ui;
+
+    fn screen() -> Screen {
+        Screen::new(Box::new(MemoryBackend::new()), (80, 40))
+    }
+
+    fn buttons(ids: &[usize]) -> HBox {
+        ids.iter().fold(HBox::new().spacing(1), |row, id| row.child(Button::new(*id, "ok")))
+    }
+
+    // Middle of the widget with this id, once the Ui has been placed
+    fn center(ui: &Ui, id: usize) -> (u16, u16) {
+        let (_, rect) = ui.focusables().into_iter().find(|(focusable, _)| *focusable == id).unwrap();
+        ((rect.x + rect.width / 2) as u16, (rect.y + rect.height / 2) as u16)
+    }
+
+    #[test]
+    fn tab_order_wraps_around_at_either_end() {
+        let mut ui = Ui::new(buttons(&[0, 1, 2]));
+
+        ui.step_focus(-1);
+        assert_eq!(ui.focused(), Some(2));
+        ui.step_focus(1);
+        assert_eq!(ui.focused(), Some(0));
+        ui.step_focus(-1);
+        assert_eq!(ui.focused(), Some(2));
+    }
+
+    #[test]
+    fn arrows_prefer_the_widget_straight_ahead() {
+        let grid = (0..6).fold(Grid::new(3).gap(2, 1), |grid, id| grid.child(Button::new(id, "ok")));
+        let mut ui = Ui::new(grid);
+        ui.place(&screen());
+
+        ui.focus(Some(0));
+        ui.move_focus(0, 1);
+        assert_eq!(ui.focused(), Some(3));
+        ui.move_focus(1, 0);
+        assert_eq!(ui.focused(), Some(4));
+        ui.move_focus(0, -1);
+        assert_eq!(ui.focused(), Some(1));
+
+        // Nothing further that way, so focus stays put
+        ui.focus(Some(5));
+        ui.move_focus(1, 0);
+        assert_eq!(ui.focused(), Some(5));
+    }
+
+    #[test]
+    fn a_new_root_keeps_focus_while_the_widget_is_still_there() {
+        let mut ui = Ui::new(buttons(&[0, 1, 2]));
+        ui.focus(Some(1));
+
+        ui.set_root(buttons(&[1, 2]));
+        assert_eq!(ui.focused(), Some(1));
+
+        ui.set_root(buttons(&[0, 2]));
+        assert_eq!(ui.focused(), None);
+    }
+
+    #[test]
+    fn a_click_needs_the_press_and_release_on_the_same_widget() {
+        let screen = screen();
+        let mut ui = Ui::new(buttons(&[0, 1]));
+        ui.place(&screen);
+        let ((x0, y0), (x1, y1)) = (center(&ui, 0), center(&ui, 1));
+
+        let frames: Vec<Vec<Event>> = vec![
+            vec![mouse_down(MouseButton::Left, x0, y0)],
+            vec![mouse_up(MouseButton::Left, x1, y1)],
+            vec![mouse_down(MouseButton::Left, x1, y1)],
+            vec![mouse_up(MouseButton::Left, x1, y1)],
+        ];
+        let events = frames.into_iter().fold(ScriptedEvents::new(), |events, frame| events.frame(0.1, frame));
+        let mut input = Input::with_source(Box::new(events));
+
+        let mut clicks = Vec::new();
+        for _ in 0..4 {
+            input.update().unwrap();
+            clicks.push(ui.update(&screen, &input));
+        }
+        assert_eq!(clicks, vec![None, None, None, Some(1)]);
+    }
+}
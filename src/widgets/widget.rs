@@ -0,0 +1,91 @@
+use crossterm::style;
+use crossterm::style::Attributes;
+use crate::input::Input;
+use crate::rect::Rect;
+use crate::screen::Screen;
+
+// What the Ui knows about the mouse and keyboard, for widgets to draw themselves by
+pub struct Interaction {
+    pub focused: Option<usize>,
+    pub hovered: Option<usize>,
+    pub mouse_down: bool,
+}
+
+impl Interaction {
+    // Hovered or focused widgets stand out, and turn the pressed color while the mouse is held on them
+    pub fn color(&self, id: Option<usize>, screen: &Screen, normal: style::Color) -> style::Color {
+        match id {
+            Some(_) if id == self.hovered && self.mouse_down => screen.theme().pressed,
+            Some(_) if id == self.hovered || id == self.focused => screen.theme().hover,
+            _ => normal,
+        }
+    }
+}
+
+// Overrides for the theme's colors, anything left out comes from the theme
+#[derive(Clone, Copy, Default)]
+pub struct Style {
+    pub color: Option<style::Color>,
+    pub background: Option<style::Color>,
+    pub attributes: Attributes,
+}
+
+impl Style {
+    pub fn color(&self, screen: &Screen) -> style::Color {
+        self.color.unwrap_or(screen.theme().text)
+    }
+}
+
+pub trait Widget {
+    // Room the widget would like, as (width, height)
+    fn measure(&self) -> (i32, i32);
+
+    // Takes up this area, containers share it out among their children
+    fn layout(&mut self, area: Rect);
+
+    fn area(&self) -> Rect;
+
+    fn draw(&self, screen: &mut Screen, interaction: &Interaction);
+
+    // Widgets with an id can be hovered, focused and clicked
+    fn id(&self) -> Option<usize> {
+        None
+    }
+
+    fn children(&self) -> &[Box<dyn Widget>] {
+        &[]
+    }
+
+    fn children_mut(&mut self) -> &mut [Box<dyn Widget>] {
+        &mut []
+    }
+
+    // The focused widget gets keys before they're used to move focus, true if it used them
+    fn handle_keys(&mut self, _input: &Input) -> bool {
+        false
+    }
+
+    // The mouse wheel turned over this widget, true if it used it
+    fn scroll(&mut self, _delta: i32) -> bool {
+        false
+    }
+}
+
+// Rounded border just inside the area, with the inside cleared
+pub fn draw_border(screen: &mut Screen, area: Rect, color: style::Color) {
+    for y in area.y..area.bottom() {
+        for x in area.x..area.right() {
+            let c = match (x == area.x, x == area.right() - 1, y == area.y, y == area.bottom() - 1) {
+                (true, _, true, _) => '╭',
+                (_, true, true, _) => '╮',
+                (true, _, _, true) => '╰',
+                (_, true, _, true) => '╯',
+                (true, _, _, _) | (_, true, _, _) => '│',
+                (_, _, true, _) | (_, _, _, true) => '─',
+                _ => ' ',
+            };
+            screen.set_cell(x, y, c, color);
+            screen.set_background(x, y, screen.theme().background);
+        }
+    }
+}